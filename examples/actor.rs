@@ -6,14 +6,14 @@ use tokio::time;
 async fn main() {
     // Spawn the actor as a task on the default runtime. This returns a handle to
     // the actor that we can use to communicate with it from other tasks.
-    let mut actor = MyActor::default().spawn();
+    let actor = MyActor::default().spawn();
 
     // Use the handle to call the `add_count` method. Under the hood, this is using
     // channels and message passing to communicate between tasks/threads, but
     // thespian hides those implementation details and provides a simple, await-aware
     // way to communicate with the actor.
     for _ in 0..10 {
-        let id = actor.add_count(1).unwrap().await;
+        let id = actor.add_count(1).unwrap().await.unwrap();
         println!("New count: {}", id);
     }
 }
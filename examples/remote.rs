@@ -7,7 +7,7 @@ async fn main() {
     let (builder, remote) = StageBuilder::new();
     let actor = MyActor { remote, count: 0 };
     let stage = builder.finish(actor);
-    let mut actor = stage.proxy();
+    let actor = stage.proxy();
     tokio::spawn(stage.run());
 
     // Use the handle to call the `add_count` method. Under the hood, this is using
@@ -15,7 +15,7 @@ async fn main() {
     // thespian hides those implementation details and provides a simple, await-aware
     // way to communicate with the actor.
     for _ in 0..10 {
-        let id = actor.add_count(1).unwrap().await;
+        let id = actor.add_count(1).unwrap().await.unwrap();
         println!("New count: {}", id);
     }
 }
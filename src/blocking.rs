@@ -0,0 +1,56 @@
+//! Support for sending messages from synchronous code by blocking the current thread.
+
+use futures::{executor, future, prelude::*};
+use std::cell::Cell;
+
+thread_local! {
+    /// Set while a stage is being polled on the current thread.
+    static IN_ACTOR: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs a future to completion on the current thread, blocking until it completes.
+///
+/// # Panics
+///
+/// Panics if called while running an actor, since blocking the thread would keep the
+/// actor from making progress, deadlocking if the future waits on the actor itself.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    if IN_ACTOR.with(Cell::get) {
+        panic!(
+            "Blocking proxy methods can't be used from within an actor, \
+             use the async proxy methods instead"
+        );
+    }
+
+    executor::block_on(future)
+}
+
+/// Wraps a stage's future so that the blocking proxy methods can detect when they're
+/// called from within an actor.
+pub(crate) async fn in_actor<F: Future>(future: F) -> F::Output {
+    futures::pin_mut!(future);
+    future::poll_fn(|cx| {
+        let _entered = Entered::enter();
+        future.as_mut().poll(cx)
+    })
+    .await
+}
+
+/// Marks that the current thread is running an actor until dropped.
+struct Entered {
+    previous: bool,
+}
+
+impl Entered {
+    fn enter() -> Self {
+        Self {
+            previous: IN_ACTOR.with(|in_actor| in_actor.replace(true)),
+        }
+    }
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        IN_ACTOR.with(|in_actor| in_actor.set(self.previous));
+    }
+}
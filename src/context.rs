@@ -0,0 +1,321 @@
+//! The context passed to an actor's message handlers.
+
+use crate::{
+    envelope::respond,
+    monitor::{self, MonitorError, Terminated},
+    proxy::{response, ProxyFor, ResponseFuture},
+    remote::{Remote, RemoteInner},
+    stage::ActorState,
+    Actor, ActorId, ActorProxy, ErasedMessage, Handler, RequestError, StreamHandler,
+};
+use futures::{
+    channel::oneshot,
+    future::BoxFuture,
+    prelude::*,
+    stream::{self, BoxStream},
+};
+use std::{fmt, mem, sync::Arc};
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+use crate::{timer, TimerHandle};
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+use std::time::Duration;
+
+/// A stream run by the stage on behalf of the actor, yielding messages for the actor to
+/// handle.
+pub(crate) type Task<A> = BoxStream<'static, Box<dyn ErasedMessage<A>>>;
+
+/// Gives an actor access to its own stage while it's handling a message.
+///
+/// Message handlers can optionally take a `ctx: &mut Context<Self>` argument in order
+/// to stop the actor, get a proxy to the actor, or spawn futures tied to the actor's
+/// lifetime. The context argument is supplied by the stage, so it doesn't appear in
+/// the generated proxy method:
+///
+/// ```
+/// use thespian::{Actor, Context};
+///
+/// #[derive(Default, Actor)]
+/// pub struct MyActor {
+///     count: usize,
+/// }
+///
+/// #[thespian::actor]
+/// impl MyActor {
+///     pub fn add(&mut self, value: usize, ctx: &mut Context<Self>) {
+///         self.count += value;
+///         if self.count >= 10 {
+///             ctx.stop();
+///         }
+///     }
+/// }
+///
+/// let stage = MyActor::default().into_stage();
+/// let proxy = stage.proxy();
+/// proxy.add(5).unwrap();
+/// ```
+///
+/// This removes the need to create the actor using a [`StageBuilder`] in order to give
+/// it a [`Remote`].
+///
+/// [`StageBuilder`]: struct.StageBuilder.html
+/// [`Remote`]: struct.Remote.html
+pub struct Context<A: Actor> {
+    pub(crate) remote: Arc<RemoteInner>,
+
+    // NOTE: The stage's own proxy lives in the context. See the comment on `Stage` for
+    // why the stage needs to hold a proxy to the actor.
+    pub(crate) proxy: ProxyFor<A>,
+
+    /// Tasks spawned by the actor that the stage hasn't started running yet.
+    tasks: Vec<Task<A>>,
+}
+
+impl<A: Actor> Context<A> {
+    pub(crate) fn new(remote: Arc<RemoteInner>, proxy: ProxyFor<A>) -> Self {
+        Self {
+            remote,
+            proxy,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Stops the actor once it has finished handling the current message.
+    ///
+    /// Any messages already in the actor's mailbox will still be handled before the
+    /// actor stops, as with [`Remote::stop`].
+    ///
+    /// [`Remote::stop`]: struct.Remote.html#method.stop
+    pub fn stop(&self) {
+        self.remote.request_stop();
+    }
+
+    /// Returns a proxy to the actor.
+    ///
+    /// Note that an actor holding onto a proxy to itself will never stop due to all of
+    /// its proxies being dropped.
+    pub fn proxy(&self) -> A::Proxy {
+        A::Proxy::new(self.proxy.clone())
+    }
+
+    /// Returns a [`Remote`] for the actor, e.g. to hand off to a future spawned by the
+    /// actor.
+    ///
+    /// [`Remote`]: struct.Remote.html
+    pub fn remote(&self) -> Remote<A> {
+        Remote::new(self.remote.clone(), &self.proxy)
+    }
+
+    pub fn state(&self) -> ActorState {
+        self.remote.state()
+    }
+
+    /// Returns the ID of the actor.
+    pub fn id(&self) -> ActorId {
+        self.remote.id()
+    }
+
+    /// Watches the actor with the given ID, delivering a [`Terminated`] message to this
+    /// actor once the other actor stops or panics.
+    ///
+    /// The ID of an actor can be obtained from any of its proxies. If the other actor
+    /// has already stopped the notification is delivered right away. Monitoring an
+    /// actor doesn't keep either actor alive, and the monitor is dropped if this actor
+    /// stops first.
+    ///
+    /// Returns [`MonitorError::NotFound`] if no actor with the given ID exists anymore.
+    ///
+    /// [`Terminated`]: struct.Terminated.html
+    /// [`MonitorError::NotFound`]: enum.MonitorError.html#variant.NotFound
+    pub fn monitor(&mut self, id: ActorId) -> Result<(), MonitorError>
+    where
+        A: Handler<Terminated>,
+    {
+        let remote = monitor::find(id)?;
+        self.tasks.push(monitor::watch(remote));
+        Ok(())
+    }
+
+    /// Links the actor with the given ID to this actor, so that when either actor stops
+    /// or panics the other is stopped too.
+    ///
+    /// Linked actors are stopped the same way as with [`stop`], so they still handle
+    /// any messages already in their mailboxes. If the other actor has already stopped,
+    /// this actor is stopped once it finishes handling the current message.
+    ///
+    /// Returns [`MonitorError::NotFound`] if no actor with the given ID exists anymore.
+    ///
+    /// [`stop`]: #method.stop
+    /// [`MonitorError::NotFound`]: enum.MonitorError.html#variant.NotFound
+    pub fn link(&self, id: ActorId) -> Result<(), MonitorError> {
+        let other = monitor::find(id)?;
+        self.remote.link(&other);
+        Ok(())
+    }
+
+    /// Spawns a future that runs alongside the actor.
+    ///
+    /// The future is run by the actor's stage rather than the runtime, so it is dropped
+    /// without completing if the actor stops first. It's polled concurrently with the
+    /// actor's message handlers, but doesn't have access to the actor itself.
+    pub fn spawn<F>(&mut self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.push(
+            stream::once(future)
+                .filter_map(|()| future::ready(None))
+                .boxed(),
+        );
+    }
+
+    /// Waits for `future` to complete without blocking the actor, then runs `then` with
+    /// the actor and the future's output.
+    ///
+    /// Awaiting a future directly in a message handler keeps the actor busy until the
+    /// future completes, so an actor that awaits a request to another actor can't
+    /// handle any messages in the meantime. If the other actor sends a request back
+    /// while handling it, the two actors deadlock waiting on each other. Using
+    /// `await_then` instead lets the handler return immediately, making the actor
+    /// reentrant: It can handle other messages while the future is pending, and picks
+    /// up where it left off once the future completes.
+    ///
+    /// Returns a future that resolves to the value returned by `then`. Request handlers
+    /// can return it in order to respond to the requester once `then` has run. The
+    /// proxy methods generated by `#[thespian::actor]` wait for it, so the requester
+    /// receives the value returned by `then` directly.
+    ///
+    /// # Ordering
+    ///
+    /// * `then` runs with exclusive access to the actor, in between handling other
+    ///   messages, so it never runs at the same time as another message handler.
+    /// * Any number of messages may be handled between the handler returning and `then`
+    ///   running, including messages sent after the one being handled. Messages from a
+    ///   single sender are still handled in the order they were sent.
+    /// * Once `future` completes, `then` is interleaved with the actor's mailbox in the
+    ///   same way as the actor's timers and attached streams. If several futures
+    ///   complete at once, their continuations run in the order the futures completed.
+    /// * If the actor stops before `future` completes, `then` never runs and the
+    ///   returned future resolves to [`RequestError::ActorStopped`].
+    ///
+    /// [`RequestError::ActorStopped`]: enum.RequestError.html#variant.ActorStopped
+    pub fn await_then<F, C, R>(&mut self, future: F, then: C) -> ResponseFuture<R>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+        C: FnOnce(&mut A, &mut Context<A>, F::Output) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_sender, result) = oneshot::channel();
+        let continuation = future.map(move |output| {
+            Box::new(Continuation {
+                output,
+                then,
+                result_sender,
+            }) as Box<dyn ErasedMessage<A>>
+        });
+
+        self.tasks.push(stream::once(continuation).boxed());
+        response(result).boxed()
+    }
+
+    /// Attaches a stream to the actor, so that each item in the stream is handled by the
+    /// actor as a message.
+    ///
+    /// See [`StreamHandler`] for more details.
+    ///
+    /// [`StreamHandler`]: trait.StreamHandler.html
+    pub fn add_stream<S>(&mut self, stream: S)
+    where
+        S: Stream + Send + 'static,
+        S::Item: 'static + Send,
+        A: StreamHandler<S::Item>,
+    {
+        self.tasks
+            .push(crate::stream::attach(stream, self.proxy.clone()));
+    }
+
+    /// Runs `f` with the actor once `duration` has elapsed.
+    ///
+    /// `f` is run in between handling other messages, so it has exclusive access to the
+    /// actor. If the actor stops before `duration` has elapsed, `f` is never run. The
+    /// pending timer doesn't keep the actor alive.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn run_later<F>(&mut self, duration: Duration, f: F) -> TimerHandle
+    where
+        F: FnOnce(&mut A, &mut Context<A>) + Send + 'static,
+    {
+        let (task, timer) = timer::later(duration, f);
+        self.tasks.push(task);
+        timer
+    }
+
+    /// Runs `f` with the actor each time `period` elapses, until the returned
+    /// [`TimerHandle`] is cancelled or the actor stops.
+    ///
+    /// The next period starts as soon as the previous one has elapsed, without waiting
+    /// for `f` to run.
+    ///
+    /// [`TimerHandle`]: struct.TimerHandle.html
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn run_interval<F>(&mut self, period: Duration, f: F) -> TimerHandle
+    where
+        F: FnMut(&mut A, &mut Context<A>) + Send + 'static,
+    {
+        let (task, timer) = timer::interval(period, f);
+        self.tasks.push(task);
+        timer
+    }
+
+    /// Takes the tasks spawned since the last time the stage checked.
+    pub(crate) fn take_tasks(&mut self) -> Vec<Task<A>> {
+        mem::take(&mut self.tasks)
+    }
+}
+
+impl<A: Actor> fmt::Debug for Context<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("state", &self.state())
+            .field("proxy", &self.proxy)
+            .finish()
+    }
+}
+
+/// The message that resumes the actor once a future passed to `await_then` completes.
+struct Continuation<T, C, R> {
+    output: T,
+    then: C,
+    result_sender: oneshot::Sender<Result<R, RequestError>>,
+}
+
+impl<A, T, C, R> ErasedMessage<A> for Continuation<T, C, R>
+where
+    A: Actor,
+    T: Send + 'static,
+    C: FnOnce(&mut A, &mut Context<A>, T) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    fn handle<'a>(self: Box<Self>, actor: &'a mut A, ctx: &'a mut Context<A>) -> BoxFuture<'a, ()> {
+        let Continuation {
+            output,
+            then,
+            result_sender,
+        } = *self;
+
+        respond(
+            result_sender,
+            async move { then(actor, ctx, output) }.boxed(),
+        )
+    }
+
+    fn handle_shared<'a>(
+        self: Box<Self>,
+        _actor: &'a A,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<A>>>
+    where
+        for<'x> &'x A: Send,
+    {
+        Err(self)
+    }
+}
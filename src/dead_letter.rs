@@ -0,0 +1,243 @@
+//! Reporting of messages that couldn't be delivered to an actor.
+//!
+//! A message becomes a dead letter if it's rejected when it's sent, e.g. because the
+//! actor's mailbox is full, or if it's discarded from the mailbox without ever being
+//! handled. Dead letters are passed to the handler configured for the actor using
+//! [`StageBuilder::dead_letters`], or to the global handler set with
+//! [`set_dead_letter_handler`] if the actor doesn't have its own.
+//!
+//! [`StageBuilder::dead_letters`]: struct.StageBuilder.html#method.dead_letters
+//! [`set_dead_letter_handler`]: fn.set_dead_letter_handler.html
+
+use crate::{
+    mailbox::Rejected, Actor, ActorId, ActorProxy, ErasedMessage, MessageErrorCause, RequestError,
+};
+use std::{
+    any::{Any, TypeId},
+    fmt,
+    sync::{Arc, RwLock},
+};
+use thiserror::Error;
+
+/// A function that receives dead letters.
+pub(crate) type DeadLetterHandler = Arc<dyn Fn(DeadLetter) + Send + Sync>;
+
+/// Sets the handler for dead letters sent to actors that don't have their own handler.
+///
+/// Replaces any previously set handler. By default, dead letters are dropped. The
+/// handler can be removed again using [`clear_dead_letter_handler`].
+///
+/// # Examples
+///
+/// ```
+/// thespian::set_dead_letter_handler(|letter| {
+///     eprintln!(
+///         "Failed to deliver {} to actor {}: {}",
+///         letter.message_type(),
+///         letter.actor(),
+///         letter.reason(),
+///     );
+/// });
+/// ```
+///
+/// [`clear_dead_letter_handler`]: fn.clear_dead_letter_handler.html
+pub fn set_dead_letter_handler<F>(handler: F)
+where
+    F: Fn(DeadLetter) + Send + Sync + 'static,
+{
+    *GLOBAL_HANDLER.write().expect("Dead letter lock poisoned") = Some(Arc::new(handler));
+}
+
+/// Removes the handler set with [`set_dead_letter_handler`], so that dead letters sent
+/// to actors that don't have their own handler are dropped again.
+///
+/// [`set_dead_letter_handler`]: fn.set_dead_letter_handler.html
+pub fn clear_dead_letter_handler() {
+    *GLOBAL_HANDLER.write().expect("Dead letter lock poisoned") = None;
+}
+
+static GLOBAL_HANDLER: RwLock<Option<DeadLetterHandler>> = RwLock::new(None);
+
+/// Passes a message that couldn't be delivered to the appropriate handler.
+///
+/// Uses the actor's own handler if it has one, falling back to the global handler.
+pub(crate) fn report<A: Actor>(
+    handler: Option<DeadLetterHandler>,
+    actor: ActorId,
+    message: Box<dyn ErasedMessage<A>>,
+    reason: DeadLetterReason,
+) {
+    let handler = handler.or_else(|| {
+        GLOBAL_HANDLER
+            .read()
+            .expect("Dead letter lock poisoned")
+            .clone()
+    });
+
+    match handler {
+        Some(handler) => handler(DeadLetter::new(actor, message, reason)),
+        None => message.reject(reason.request_error()),
+    }
+}
+
+/// A message that couldn't be delivered to an actor.
+///
+/// If the dead letter is dropped without being redelivered and the message was a
+/// request, the requester is notified that the request failed.
+pub struct DeadLetter {
+    actor: ActorId,
+    message_type: &'static str,
+    reason: DeadLetterReason,
+
+    // NOTE: This is only `None` once the message has been taken for redelivery.
+    message: Option<Box<dyn Undelivered>>,
+}
+
+impl DeadLetter {
+    fn new<A: Actor>(
+        actor: ActorId,
+        message: Box<dyn ErasedMessage<A>>,
+        reason: DeadLetterReason,
+    ) -> Self {
+        Self {
+            actor,
+            message_type: message.type_name(),
+            reason,
+            message: Some(Box::new(Letter(message))),
+        }
+    }
+
+    /// The ID of the actor the message was sent to.
+    pub fn actor(&self) -> ActorId {
+        self.actor
+    }
+
+    /// The name of the message type, as given by [`Message::type_name`].
+    ///
+    /// [`Message::type_name`]: trait.Message.html#method.type_name
+    pub fn message_type(&self) -> &'static str {
+        self.message_type
+    }
+
+    /// Why the message couldn't be delivered.
+    pub fn reason(&self) -> DeadLetterReason {
+        self.reason
+    }
+
+    /// Attempts to deliver the message to another actor of the same type.
+    ///
+    /// Returns the dead letter back if `proxy` refers to a different type of actor than
+    /// the one the message was originally sent to, or if the message can't be delivered
+    /// to the new actor either. The returned dead letter isn't passed to any dead
+    /// letter handlers.
+    pub fn redeliver<P: ActorProxy>(mut self, proxy: &P) -> Result<(), DeadLetter> {
+        let is_actor_type = self
+            .message
+            .as_ref()
+            .map(|message| message.actor_type() == TypeId::of::<P::Actor>())
+            .unwrap_or(false);
+        if !is_actor_type {
+            return Err(self);
+        }
+
+        let message = self.message.take().unwrap().into_any();
+        let Letter(message) = *message
+            .downcast::<Letter<P::Actor>>()
+            .expect("Dead letter has the wrong actor type");
+
+        let proxy = proxy.inner();
+        proxy
+            .sink
+            .try_push(message, |message| message)
+            .map_err(|Rejected { message, reason }| {
+                DeadLetter::new(proxy.id(), message, reason.into())
+            })
+    }
+}
+
+impl fmt::Debug for DeadLetter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadLetter")
+            .field("actor", &self.actor)
+            .field("message_type", &self.message_type)
+            .field("reason", &self.reason)
+            .finish()
+    }
+}
+
+impl Drop for DeadLetter {
+    fn drop(&mut self) {
+        if let Some(message) = self.message.take() {
+            message.reject(self.reason.request_error());
+        }
+    }
+}
+
+/// The reason a message couldn't be delivered to an actor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[non_exhaustive]
+pub enum DeadLetterReason {
+    /// The message was sent after the actor had stopped.
+    #[error("Actor was stopped")]
+    ActorStopped,
+
+    /// The message was rejected because the actor's mailbox was full.
+    #[error("Message box was full")]
+    MailboxFull,
+
+    /// The message was discarded from a full mailbox to make room for a newer message,
+    /// due to the mailbox's [`OverflowPolicy::DropOldest`] policy.
+    ///
+    /// [`OverflowPolicy::DropOldest`]: enum.OverflowPolicy.html#variant.DropOldest
+    #[error("Message was dropped from a full mailbox")]
+    Displaced,
+
+    /// The message was still in the actor's mailbox when the actor stopped without
+    /// handling it, e.g. because it panicked.
+    #[error("Actor stopped before handling the message")]
+    Discarded,
+}
+
+impl DeadLetterReason {
+    /// The error a requester is notified with if the message was a request.
+    fn request_error(self) -> RequestError {
+        match self {
+            DeadLetterReason::Displaced => RequestError::Dropped,
+            _ => RequestError::ActorStopped,
+        }
+    }
+}
+
+impl From<MessageErrorCause> for DeadLetterReason {
+    fn from(cause: MessageErrorCause) -> Self {
+        match cause {
+            MessageErrorCause::MailboxFull => DeadLetterReason::MailboxFull,
+            _ => DeadLetterReason::ActorStopped,
+        }
+    }
+}
+
+/// Type-erased interface to an undelivered message, independent of the actor type.
+trait Undelivered: Send {
+    fn actor_type(&self) -> TypeId;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send>;
+
+    fn reject(self: Box<Self>, error: RequestError);
+}
+
+struct Letter<A: Actor>(Box<dyn ErasedMessage<A>>);
+
+impl<A: Actor> Undelivered for Letter<A> {
+    fn actor_type(&self) -> TypeId {
+        TypeId::of::<A>()
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send> {
+        self
+    }
+
+    fn reject(self: Box<Self>, error: RequestError) {
+        self.0.reject(error);
+    }
+}
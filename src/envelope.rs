@@ -7,9 +7,9 @@
 //! * The message must be bundled with oneshot channel in order to send the message
 //!   response back to the sender.
 
-use crate::{Actor, ErasedMessage, Message};
+use crate::{Actor, Context, ErasedMessage, Message, RequestError};
 use futures::{channel::oneshot, future::BoxFuture, prelude::*};
-use std::fmt;
+use std::{fmt, panic::AssertUnwindSafe};
 
 pub(crate) enum Envelope<A: Actor> {
     Message(Box<dyn ErasedMessage<A>>),
@@ -26,30 +26,106 @@ impl<A: Actor> fmt::Debug for Envelope<A> {
 }
 
 impl<M: Message> ErasedMessage<M::Actor> for M {
-    fn handle(self: Box<Self>, actor: &mut M::Actor) -> BoxFuture<'_, ()> {
+    fn handle<'a>(
+        self: Box<Self>,
+        actor: &'a mut M::Actor,
+        ctx: &'a mut Context<M::Actor>,
+    ) -> BoxFuture<'a, ()> {
         // TODO: Remove the extra boxing here. In theory, we should be able to constrain
         // this impl to only messages where `Output == ()`, but that's not currently
         // supported. See https://github.com/rust-lang/rust/issues/20041 for more
         // information.
-        Message::handle(*self, actor).map(|_| {}).boxed()
+        Message::handle(*self, actor, ctx).map(|_| {}).boxed()
+    }
+
+    fn handle_shared<'a>(
+        self: Box<Self>,
+        actor: &'a M::Actor,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<M::Actor>>>
+    where
+        for<'x> &'x M::Actor: Send,
+    {
+        match Message::handle_shared(*self, actor) {
+            Ok(handling) => Ok(handling.map(|_| {}).boxed()),
+            Err(message) => Err(Box::new(message)),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        M::type_name()
     }
 }
 
 pub(crate) struct RequestEnvelope<M: Message> {
-    pub(crate) result_sender: oneshot::Sender<M::Output>,
+    pub(crate) result_sender: oneshot::Sender<Result<M::Output, RequestError>>,
     pub(crate) message: M,
 }
 
 impl<M: Message> ErasedMessage<M::Actor> for RequestEnvelope<M> {
-    fn handle(self: Box<Self>, actor: &mut M::Actor) -> BoxFuture<'_, ()> {
-        async move {
-            let result = self.message.handle(actor).await;
-
-            // If the message sender has dropped the handle the attempt to send the result will
-            // fail. In that cases, there's nothing we can reasonably do other than discard the
-            // result.
-            let _ = self.result_sender.send(result);
+    fn handle<'a>(
+        self: Box<Self>,
+        actor: &'a mut M::Actor,
+        ctx: &'a mut Context<M::Actor>,
+    ) -> BoxFuture<'a, ()> {
+        let RequestEnvelope {
+            result_sender,
+            message,
+        } = *self;
+
+        respond(result_sender, message.handle(actor, ctx))
+    }
+
+    fn handle_shared<'a>(
+        self: Box<Self>,
+        actor: &'a M::Actor,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<M::Actor>>>
+    where
+        for<'x> &'x M::Actor: Send,
+    {
+        let RequestEnvelope {
+            result_sender,
+            message,
+        } = *self;
+
+        match message.handle_shared(actor) {
+            Ok(handling) => Ok(respond(result_sender, handling)),
+            Err(message) => Err(Box::new(RequestEnvelope {
+                result_sender,
+                message,
+            })),
+        }
+    }
+
+    fn reject(self: Box<Self>, error: RequestError) {
+        let _ = self.result_sender.send(Err(error));
+    }
+
+    fn type_name(&self) -> &'static str {
+        M::type_name()
+    }
+}
+
+/// Runs a request's handler, sending the result back to the requester.
+pub(crate) fn respond<'a, T: Send + 'a>(
+    result_sender: oneshot::Sender<Result<T, RequestError>>,
+    handling: BoxFuture<'a, T>,
+) -> BoxFuture<'a, ()> {
+    async move {
+        // If the message sender has dropped the handle the attempt to send the result will
+        // fail. In that cases, there's nothing we can reasonably do other than discard the
+        // result.
+        match AssertUnwindSafe(handling).catch_unwind().await {
+            Ok(result) => {
+                let _ = result_sender.send(Ok(result));
+            }
+
+            // Notify the requester that the actor panicked, then resume unwinding so that
+            // the stage can handle the panic as well.
+            Err(payload) => {
+                let _ = result_sender.send(Err(RequestError::from_panic(&*payload)));
+                std::panic::resume_unwind(payload);
+            }
         }
-        .boxed()
     }
+    .boxed()
 }
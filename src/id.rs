@@ -0,0 +1,63 @@
+//! Unique identifiers for actors.
+
+use crate::remote::RemoteInner;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock, Weak,
+    },
+};
+
+/// Uniquely identifies an actor within the process.
+///
+/// Each actor is assigned an ID when its [`StageBuilder`] is created, and the ID is
+/// never reused, even after the actor has stopped. A supervised actor keeps the same
+/// ID when it is restarted.
+///
+/// [`StageBuilder`]: struct.StageBuilder.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ActorId(u64);
+
+impl ActorId {
+    /// Allocates a new, unused ID.
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        ActorId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl fmt::Display for ActorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Makes the actor with the given remote discoverable by its ID.
+///
+/// Only a weak reference to the remote is held, and the entry is removed once the
+/// remote is dropped.
+pub(crate) fn register(remote: &Arc<RemoteInner>) {
+    remotes().insert(remote.id(), Arc::downgrade(remote));
+}
+
+pub(crate) fn deregister(id: ActorId) {
+    remotes().remove(&id);
+}
+
+/// Finds the remote for the actor with the given ID, if it still exists.
+pub(crate) fn find(id: ActorId) -> Option<Arc<RemoteInner>> {
+    // NOTE: The lock is released before the upgraded remote can be dropped, since
+    // dropping the last reference to a remote deregisters it.
+    let weak = remotes().get(&id)?.clone();
+    weak.upgrade()
+}
+
+fn remotes() -> MutexGuard<'static, HashMap<ActorId, Weak<RemoteInner>>> {
+    static REMOTES: OnceLock<Mutex<HashMap<ActorId, Weak<RemoteInner>>>> = OnceLock::new();
+    REMOTES
+        .get_or_init(Default::default)
+        .lock()
+        .expect("Actor ID lock poisoned")
+}
@@ -1,16 +1,29 @@
-use futures::channel::mpsc;
-use log::*;
+use futures::{future::BoxFuture, prelude::*};
+use std::{any::Any, fmt};
 use thiserror::Error;
 
+mod blocking;
+mod context;
+mod dead_letter;
 mod envelope;
+mod id;
+mod mailbox;
 mod message;
+mod monitor;
 mod proxy;
+mod recipient;
+mod registry;
 mod remote;
 mod stage;
+mod stream;
+mod supervisor;
+mod system;
 
 // Helper module for abstracting over different runtimes.
 #[cfg(any(feature = "tokio", feature = "async-std"))]
 mod runtime;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod timer;
 
 // Re-export the futures crate so that it can be referenced by the generated code.
 // We don't want this to be part of the crate's stable API, though, so we hide it in
@@ -18,12 +31,38 @@ mod runtime;
 #[doc(hidden)]
 pub use futures;
 
-pub use crate::{message::*, proxy::*, remote::*, stage::*};
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use crate::timer::*;
+pub use crate::{
+    context::*,
+    dead_letter::*,
+    id::ActorId,
+    mailbox::{OverflowPolicy, DEFAULT_MAILBOX_CAPACITY},
+    message::*,
+    monitor::*,
+    proxy::*,
+    recipient::*,
+    registry::*,
+    remote::*,
+    stage::*,
+    stream::*,
+    supervisor::*,
+    system::*,
+};
 pub use thespian_derive::*;
 
 pub trait Actor: 'static + Sized + Send {
     type Proxy: ActorProxy<Actor = Self>;
 
+    /// The default capacity of the actor's mailbox.
+    ///
+    /// Must be greater than 0. This can be overridden for an individual stage using
+    /// [`StageBuilder::capacity`], or set when deriving `Actor` using
+    /// `#[thespian(mailbox_capacity = ...)]`.
+    ///
+    /// [`StageBuilder::capacity`]: struct.StageBuilder.html#method.capacity
+    const MAILBOX_CAPACITY: usize = DEFAULT_MAILBOX_CAPACITY;
+
     fn into_stage(self) -> Stage<Self> {
         let (builder, _) = StageBuilder::new();
         builder.finish(self)
@@ -48,6 +87,101 @@ pub trait Actor: 'static + Sized + Send {
 
         proxy
     }
+
+    /// Spawns the actor onto the runtime, returning a proxy and a [`JoinHandle`] that
+    /// resolves once the actor has finished running.
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    fn spawn_with_handle(self) -> (Self::Proxy, JoinHandle) {
+        let stage = self.into_stage();
+        let proxy = stage.proxy();
+        let handle = stage.join_handle();
+        crate::runtime::spawn(stage.run());
+        (proxy, handle)
+    }
+
+    /// Spawns the actor onto the runtime, registering it under `name`.
+    ///
+    /// Other code can then get a proxy to the actor using [`lookup`]. The actor is
+    /// removed from the registry once it stops. Fails if another running actor is
+    /// already registered under `name`.
+    ///
+    /// [`lookup`]: fn.lookup.html
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    fn spawn_named(
+        self,
+        name: impl Into<String>,
+    ) -> std::result::Result<Self::Proxy, RegistryError> {
+        let (builder, _) = StageBuilder::new();
+        Ok(builder.name(name)?.spawn(self))
+    }
+
+    /// Called once the actor has started running, before it handles any messages.
+    ///
+    /// This is the place to perform any asynchronous setup for the actor, e.g. opening
+    /// a database connection. Messages sent to the actor before it has started will be
+    /// queued and handled once this hook completes.
+    fn started(&mut self) -> BoxFuture<'_, ()> {
+        future::ready(()).boxed()
+    }
+
+    /// Called once the actor has stopped, either because it was asked to stop or
+    /// because all proxies to it have been dropped.
+    ///
+    /// No new messages will be accepted once this hook is called, but any messages
+    /// still in the actor's mailbox will be handled after it completes.
+    fn stopping(&mut self) -> BoxFuture<'_, ()> {
+        future::ready(()).boxed()
+    }
+
+    /// Called after the actor has handled all remaining messages, right before the
+    /// actor is dropped.
+    ///
+    /// This is the place to flush any buffered state or close connections opened in
+    /// [`started`].
+    ///
+    /// [`started`]: #method.started
+    fn stopped(&mut self) -> BoxFuture<'_, ()> {
+        future::ready(()).boxed()
+    }
+
+    /// Returns shared access to the actor, allowing read-only messages to be handled
+    /// concurrently.
+    ///
+    /// By default every message is given exclusive access to the actor, so messages are
+    /// handled one at a time. Actors that are `Sync` can instead allow messages that
+    /// only read from the actor, i.e. handlers that take `&self`, to be handled
+    /// concurrently: Consecutive read-only messages run at the same time, while any
+    /// other message waits for the running reads to complete and then runs on its own.
+    ///
+    /// When deriving `Actor`, opt in using `#[thespian(concurrent_reads)]`. When
+    /// implementing `Actor` manually, override this method to return a
+    /// [`SharedAccess`]:
+    ///
+    /// ```
+    /// use thespian::{Actor, SharedAccess};
+    ///
+    /// #[derive(Actor)]
+    /// #[thespian(proxy_only)]
+    /// pub struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Proxy = MyActorProxy;
+    ///
+    ///     fn shared_access(&self) -> Option<SharedAccess<'_, Self>> {
+    ///         Some(SharedAccess::new(self))
+    ///     }
+    /// }
+    ///
+    /// #[thespian::actor]
+    /// impl MyActor {}
+    /// ```
+    ///
+    /// [`SharedAccess`]: struct.SharedAccess.html
+    fn shared_access(&self) -> Option<SharedAccess<'_, Self>> {
+        None
+    }
 }
 
 pub type Result<T> = std::result::Result<T, MessageError>;
@@ -58,21 +192,151 @@ pub struct MessageError {
     cause: MessageErrorCause,
 }
 
-impl<T> From<mpsc::TrySendError<T>> for MessageError {
-    fn from(from: mpsc::TrySendError<T>) -> Self {
-        let cause = if from.is_full() {
-            MessageErrorCause::MailboxFull
-        } else if from.is_disconnected() {
-            MessageErrorCause::ActorStopped
-        } else {
-            warn!("Unknown cause of send error: {:?}", from);
-            MessageErrorCause::Unknown
-        };
+impl MessageError {
+    /// Returns the reason the message couldn't be sent.
+    pub fn cause(&self) -> &MessageErrorCause {
+        &self.cause
+    }
+}
 
+impl From<MessageErrorCause> for MessageError {
+    fn from(cause: MessageErrorCause) -> Self {
         MessageError { cause }
     }
 }
 
+/// Error returned when a message can't be sent to an actor, handing back the message
+/// that couldn't be sent.
+///
+/// This allows the caller to retry sending the message, e.g. once there is space in the
+/// actor's mailbox, without having to clone the message beforehand:
+///
+/// ```
+/// use thespian::{Actor, StageBuilder};
+///
+/// #[derive(Default, Actor)]
+/// pub struct Writer {
+///     written: usize,
+/// }
+///
+/// #[thespian::actor]
+/// impl Writer {
+///     pub fn write(&mut self, buffer: Vec<u8>) {
+///         self.written += buffer.len();
+///     }
+/// }
+///
+/// let (builder, _) = StageBuilder::new();
+/// let stage = builder.capacity(1).finish(Writer::default());
+/// let writer = stage.proxy();
+/// writer.write(vec![0; 1024]).unwrap();
+///
+/// let error = writer.write(vec![1; 1024]).unwrap_err();
+/// let buffer = error.into_inner();
+/// assert_eq!(vec![1; 1024], buffer);
+/// ```
+///
+/// For methods on generated proxies, the message is the method's arguments, or a tuple
+/// of the arguments if the method takes more than one.
+///
+/// If the error is dropped without taking the message back using [`into_inner`], the
+/// message is reported as a [dead letter]. A `SendError` can be converted into a
+/// [`MessageError`], e.g. using `?`, in which case the message is reported as well.
+///
+/// [`into_inner`]: #method.into_inner
+/// [dead letter]: struct.DeadLetter.html
+/// [`MessageError`]: struct.MessageError.html
+pub struct SendError<M> {
+    cause: MessageErrorCause,
+
+    // NOTE: The message is only `None` once it has been taken back by the caller, or
+    // while the error is being dropped.
+    message: Option<M>,
+
+    /// Reports the message as a dead letter if the error is dropped.
+    #[allow(clippy::type_complexity)]
+    report: Option<Box<dyn FnOnce(M) + Send + Sync>>,
+}
+
+impl<M> SendError<M> {
+    pub(crate) fn new<F>(message: M, cause: MessageErrorCause, report: F) -> Self
+    where
+        F: FnOnce(M) + Send + Sync + 'static,
+    {
+        Self {
+            cause,
+            message: Some(message),
+            report: Some(Box::new(report)),
+        }
+    }
+
+    /// Returns the reason the message couldn't be sent.
+    pub fn cause(&self) -> &MessageErrorCause {
+        &self.cause
+    }
+
+    /// Returns a reference to the message that couldn't be sent.
+    pub fn message(&self) -> &M {
+        self.message.as_ref().unwrap()
+    }
+
+    /// Takes back the message that couldn't be sent.
+    pub fn into_inner(mut self) -> M {
+        self.message.take().unwrap()
+    }
+
+    /// Converts the message that couldn't be sent, e.g. from the message type generated
+    /// for a proxy method to the method's arguments.
+    ///
+    /// `back` is used to convert the message back in case it's reported as a dead
+    /// letter.
+    #[doc(hidden)]
+    pub fn map_message<N, F, G>(mut self, into: F, back: G) -> SendError<N>
+    where
+        M: 'static,
+        F: FnOnce(M) -> N,
+        G: FnOnce(N) -> M + Send + Sync + 'static,
+    {
+        let report = self.report.take().unwrap();
+        SendError::new(
+            into(self.message.take().unwrap()),
+            self.cause.clone(),
+            move |message| report(back(message)),
+        )
+    }
+}
+
+impl<M> fmt::Debug for SendError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError")
+            .field("cause", &self.cause)
+            .field("message", &std::any::type_name::<M>())
+            .finish()
+    }
+}
+
+impl<M> fmt::Display for SendError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.cause.fmt(f)
+    }
+}
+
+impl<M> std::error::Error for SendError<M> {}
+
+impl<M> From<SendError<M>> for MessageError {
+    fn from(error: SendError<M>) -> Self {
+        error.cause.clone().into()
+    }
+}
+
+impl<M> Drop for SendError<M> {
+    fn drop(&mut self) {
+        if let (Some(message), Some(report)) = (self.message.take(), self.report.take()) {
+            report(message);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 #[non_exhaustive]
 pub enum MessageErrorCause {
@@ -85,3 +349,44 @@ pub enum MessageErrorCause {
     #[error("Unknown reason for message error")]
     Unknown,
 }
+
+/// Error returned when an actor fails to respond to a request.
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum RequestError {
+    /// The actor panicked while handling the request.
+    ///
+    /// Contains the message from the panic payload, if the payload was a string.
+    #[error("Actor panicked while handling request: {0}")]
+    Panicked(String),
+
+    /// The actor stopped without handling the request.
+    #[error("Actor stopped before responding to request")]
+    ActorStopped,
+
+    /// The request was discarded from the actor's mailbox to make room for newer
+    /// messages.
+    #[error("Request was dropped from the actor's mailbox before it was handled")]
+    Dropped,
+
+    /// The actor didn't respond to the request before the timeout elapsed.
+    #[error("Timed out waiting for the actor to respond to request")]
+    TimedOut,
+}
+
+impl RequestError {
+    pub(crate) fn from_panic(payload: &(dyn Any + Send)) -> Self {
+        RequestError::Panicked(panic_message(payload))
+    }
+}
+
+/// Extracts the message from a panic payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".into()
+    }
+}
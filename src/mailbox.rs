@@ -0,0 +1,491 @@
+//! The channel used to deliver messages to an actor.
+//!
+//! `futures::channel::mpsc` only supports rejecting new messages once a bounded
+//! channel is full, and uses different sender types for bounded and unbounded
+//! channels. The mailbox instead uses a single queue shared between the senders and
+//! the stage, which allows the capacity and overflow behavior to be configured per
+//! actor.
+
+use crate::{
+    dead_letter::{self, DeadLetterHandler, DeadLetterReason},
+    envelope::*,
+    Actor, ActorId, ErasedMessage, MessageError, MessageErrorCause,
+};
+use derivative::Derivative;
+use futures::{
+    future,
+    prelude::*,
+    task::{Context, Poll, Waker},
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt, mem,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// The default capacity for an actor's mailbox.
+pub const DEFAULT_MAILBOX_CAPACITY: usize = 16;
+
+/// Determines what happens when a message is sent to an actor whose mailbox is full.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// The new message is rejected, and sending it fails with
+    /// [`MessageErrorCause::MailboxFull`].
+    ///
+    /// [`MessageErrorCause::MailboxFull`]: enum.MessageErrorCause.html#variant.MailboxFull
+    #[default]
+    RejectNewest,
+
+    /// The oldest message in the mailbox is discarded in order to make room for the new
+    /// message. If the discarded message was a request, the requester is notified that
+    /// the message was dropped.
+    DropOldest,
+
+    /// The new message waits until the actor makes room for it in the mailbox.
+    ///
+    /// Sending never fails because the mailbox is full, and never blocks the sending
+    /// thread. Instead the message is held back and added to the mailbox once the actor
+    /// has taken a message out of it, in the order the messages were sent. There is no
+    /// limit on how many messages can be held back this way.
+    Block,
+}
+
+/// Creates a new mailbox with the default configuration for the actor.
+pub(crate) fn mailbox<A: Actor>(id: ActorId) -> (MailboxSender<A>, MailboxReceiver<A>) {
+    assert!(
+        A::MAILBOX_CAPACITY > 0,
+        "Mailbox capacity must be greater than 0"
+    );
+
+    let shared = Arc::new(Shared {
+        id,
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            capacity: Some(A::MAILBOX_CAPACITY),
+            overflow: OverflowPolicy::default(),
+            proxy_dropped: false,
+            closed: false,
+            receiver: None,
+            blocked: VecDeque::new(),
+            senders: BTreeMap::new(),
+            next_key: 0,
+            dead_letters: None,
+        }),
+    });
+
+    (
+        MailboxSender {
+            shared: shared.clone(),
+        },
+        MailboxReceiver { shared },
+    )
+}
+
+struct Shared<A: Actor> {
+    /// The ID of the actor the mailbox belongs to.
+    id: ActorId,
+
+    state: Mutex<State<A>>,
+}
+
+impl<A: Actor> Shared<A> {
+    fn lock(&self) -> MutexGuard<'_, State<A>> {
+        self.state.lock().expect("Mailbox lock poisoned")
+    }
+
+    /// Reports a message that couldn't be delivered as a dead letter.
+    ///
+    /// Must not be called while holding the lock, since the dead letter handler may run
+    /// arbitrary code.
+    fn report(&self, message: Box<dyn ErasedMessage<A>>, reason: DeadLetterReason) {
+        let handler = self.lock().dead_letters.clone();
+        dead_letter::report(handler, self.id, message, reason);
+    }
+}
+
+struct State<A: Actor> {
+    queue: VecDeque<Box<dyn ErasedMessage<A>>>,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+
+    /// Set when a proxy has been dropped and the stage hasn't been notified yet.
+    proxy_dropped: bool,
+
+    closed: bool,
+
+    /// The waker for the stage, if it's waiting for a message.
+    receiver: Option<Waker>,
+
+    /// Messages sent under [`OverflowPolicy::Block`] that are waiting for space in the
+    /// mailbox, in the order they were sent.
+    ///
+    /// [`OverflowPolicy::Block`]: enum.OverflowPolicy.html#variant.Block
+    blocked: VecDeque<Box<dyn ErasedMessage<A>>>,
+
+    /// Wakers for any tasks waiting for space in the mailbox, keyed by send.
+    ///
+    /// Keys are handed out in increasing order, so the first entry is the send that has
+    /// been waiting the longest.
+    senders: BTreeMap<usize, Waker>,
+    next_key: usize,
+
+    /// The actor's own handler for dead letters, if it has one.
+    dead_letters: Option<DeadLetterHandler>,
+}
+
+impl<A: Actor> State<A> {
+    fn is_full(&self) -> bool {
+        self.capacity
+            .map(|capacity| self.queue.len() >= capacity)
+            .unwrap_or(false)
+    }
+
+    /// Removes the send that has been waiting the longest from the list of waiting
+    /// senders, returning its waker.
+    fn next_sender(&mut self) -> Option<Waker> {
+        let key = *self.senders.keys().next()?;
+        self.senders.remove(&key)
+    }
+
+    /// Adds a message to the queue, returning the stage's waker if it needs to be woken.
+    fn push(&mut self, message: Box<dyn ErasedMessage<A>>) -> Option<Waker> {
+        self.queue.push_back(message);
+        self.receiver.take()
+    }
+}
+
+/// The sending half of an actor's mailbox.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub(crate) struct MailboxSender<A: Actor> {
+    shared: Arc<Shared<A>>,
+}
+
+impl<A: Actor> MailboxSender<A> {
+    /// Attempts to add a message to the mailbox, applying the mailbox's overflow policy
+    /// if it is full.
+    ///
+    /// A rejected message is reported as a dead letter.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub(crate) fn try_send(&self, message: Box<dyn ErasedMessage<A>>) -> Result<(), MessageError> {
+        self.try_push(message, |message| message)
+            .map_err(|Rejected { message, reason }| {
+                self.shared.report(message, reason.clone().into());
+                reason.into()
+            })
+    }
+
+    /// Attempts to add a message to the mailbox, applying the mailbox's overflow policy
+    /// if it is full.
+    ///
+    /// The message is only type-erased using `erase` once it's accepted, so that a
+    /// rejected message can be handed back to the caller as-is rather than being
+    /// reported as a dead letter. A message displaced from the mailbox is still reported.
+    pub(crate) fn try_push<T>(
+        &self,
+        message: T,
+        erase: impl FnOnce(T) -> Box<dyn ErasedMessage<A>>,
+    ) -> Result<(), Rejected<T>> {
+        let mut state = self.shared.lock();
+        let mut displaced = None;
+
+        if state.closed {
+            return Err(Rejected::new(message, MessageErrorCause::ActorStopped));
+        }
+
+        if state.is_full() {
+            match state.overflow {
+                OverflowPolicy::RejectNewest => {
+                    return Err(Rejected::new(message, MessageErrorCause::MailboxFull));
+                }
+
+                OverflowPolicy::DropOldest => displaced = state.queue.pop_front(),
+
+                // NOTE: The mailbox stays full for as long as any messages are waiting, since
+                // each message taken out is immediately replaced by the next waiting one.
+                // This means new messages always wait behind the ones already waiting.
+                OverflowPolicy::Block => {
+                    state.blocked.push_back(erase(message));
+                    return Ok(());
+                }
+            }
+        }
+
+        let receiver = state.push(erase(message));
+
+        // Release the lock before reporting the displaced message or waking the stage,
+        // since either may run arbitrary code.
+        drop(state);
+        if let Some(displaced) = displaced {
+            self.shared.report(displaced, DeadLetterReason::Displaced);
+        }
+        if let Some(waker) = receiver {
+            waker.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Reports a message that couldn't be delivered to the actor as a dead letter.
+    pub(crate) fn report(&self, message: Box<dyn ErasedMessage<A>>, reason: DeadLetterReason) {
+        self.shared.report(message, reason);
+    }
+
+    /// Adds a message to the mailbox, waiting for space if the mailbox is full.
+    ///
+    /// This ignores the mailbox's overflow policy, always waiting until there is space
+    /// for the message or the mailbox is closed. Sends waiting for space are added to
+    /// the mailbox in the order they started waiting.
+    pub(crate) async fn send(
+        &self,
+        message: Box<dyn ErasedMessage<A>>,
+    ) -> Result<(), MessageError> {
+        let mut send = WaitingSend {
+            shared: &self.shared,
+            message: Some(message),
+            key: None,
+        };
+        future::poll_fn(|cx| send.poll(cx)).await
+    }
+
+    /// Notifies the stage that a proxy has been dropped.
+    ///
+    /// This never fails or blocks, regardless of how full the mailbox is.
+    pub(crate) fn notify_proxy_dropped(&self) {
+        let mut state = self.shared.lock();
+        if state.closed {
+            return;
+        }
+
+        state.proxy_dropped = true;
+        let receiver = state.receiver.take();
+        drop(state);
+
+        if let Some(waker) = receiver {
+            waker.wake();
+        }
+    }
+}
+
+impl<A: Actor> fmt::Debug for MailboxSender<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.shared.lock();
+        f.debug_struct("MailboxSender")
+            .field("len", &state.queue.len())
+            .field("blocked", &state.blocked.len())
+            .field("capacity", &state.capacity)
+            .field("overflow", &state.overflow)
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
+
+/// The receiving half of an actor's mailbox, held by the stage.
+pub(crate) struct MailboxReceiver<A: Actor> {
+    shared: Arc<Shared<A>>,
+}
+
+impl<A: Actor> MailboxReceiver<A> {
+    /// Sets the maximum number of messages the mailbox can hold, or `None` for an
+    /// unbounded mailbox.
+    pub(crate) fn set_capacity(&self, capacity: Option<usize>) {
+        self.shared.lock().capacity = capacity;
+    }
+
+    pub(crate) fn set_overflow(&self, overflow: OverflowPolicy) {
+        self.shared.lock().overflow = overflow;
+    }
+
+    pub(crate) fn set_dead_letters(&self, handler: DeadLetterHandler) {
+        self.shared.lock().dead_letters = Some(handler);
+    }
+
+    /// Reports a message taken from the mailbox that won't be handled as a dead letter.
+    pub(crate) fn discard(&self, message: Box<dyn ErasedMessage<A>>) {
+        self.shared.report(message, DeadLetterReason::Discarded);
+    }
+
+    /// Discards any messages remaining in the mailbox, reporting them as dead letters.
+    pub(crate) fn discard_remaining(&self) {
+        // Take the messages out of the mailbox first so that they're reported outside of
+        // the lock.
+        let (queue, blocked) = {
+            let mut state = self.shared.lock();
+            (mem::take(&mut state.queue), mem::take(&mut state.blocked))
+        };
+        for message in queue.into_iter().chain(blocked) {
+            self.discard(message);
+        }
+    }
+
+    /// Closes the mailbox so that no new messages can be sent.
+    ///
+    /// Any messages already in the mailbox, including messages waiting for space, can
+    /// still be received.
+    pub(crate) fn close(&mut self) {
+        let senders = {
+            let mut state = self.shared.lock();
+            state.closed = true;
+            mem::take(&mut state.senders)
+        };
+
+        // Wake any waiting senders so that they can see the mailbox has been closed.
+        for waker in senders.into_values() {
+            waker.wake();
+        }
+    }
+}
+
+impl<A: Actor> Stream for MailboxReceiver<A> {
+    type Item = Envelope<A>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.shared.lock();
+
+        if let Some(message) = state.queue.pop_front() {
+            // Taking a message out makes room for the next message waiting for space. Held
+            // back messages are moved in directly, otherwise the send that has been waiting
+            // the longest is woken to add its message.
+            let sender = match state.blocked.pop_front() {
+                Some(blocked) => {
+                    state.queue.push_back(blocked);
+                    None
+                }
+                None => state.next_sender(),
+            };
+            drop(state);
+
+            if let Some(waker) = sender {
+                waker.wake();
+            }
+
+            return Poll::Ready(Some(Envelope::Message(message)));
+        }
+
+        if state.proxy_dropped {
+            state.proxy_dropped = false;
+            return Poll::Ready(Some(Envelope::ProxyDropped));
+        }
+
+        if state.closed {
+            return Poll::Ready(None);
+        }
+
+        state.receiver = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<A: Actor> Drop for MailboxReceiver<A> {
+    fn drop(&mut self) {
+        self.close();
+        self.discard_remaining();
+    }
+}
+
+/// A message waiting for space in the mailbox, created by [`MailboxSender::send`].
+///
+/// [`MailboxSender::send`]: struct.MailboxSender.html#method.send
+struct WaitingSend<'a, A: Actor> {
+    shared: &'a Shared<A>,
+    message: Option<Box<dyn ErasedMessage<A>>>,
+
+    /// The key for this send's waker in the mailbox's list of waiting senders, once the
+    /// send has had to wait.
+    ///
+    /// The key is kept after the send is woken, so that the send keeps its place in line
+    /// if it finds the mailbox full again.
+    key: Option<usize>,
+}
+
+impl<'a, A: Actor> WaitingSend<'a, A> {
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), MessageError>> {
+        let mut state = self.shared.lock();
+
+        if state.closed {
+            if let Some(key) = self.key {
+                state.senders.remove(&key);
+            }
+            drop(state);
+            if let Some(message) = self.message.take() {
+                self.shared.report(message, DeadLetterReason::ActorStopped);
+            }
+            return Poll::Ready(Err(MessageErrorCause::ActorStopped.into()));
+        }
+
+        // Wait if the mailbox is full, or if there's room but a send that has been waiting
+        // longer hasn't taken it yet.
+        let first = state.senders.keys().next().copied();
+        let waiting_behind = match (first, self.key) {
+            (Some(first), Some(key)) => first < key,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if state.is_full() || waiting_behind {
+            // Replace the waker from any previous poll rather than adding a new one, so that
+            // repeatedly polling the send doesn't accumulate wakers.
+            let key = match self.key {
+                Some(key) => key,
+                None => {
+                    state.next_key += 1;
+                    state.next_key
+                }
+            };
+            self.key = Some(key);
+            state.senders.insert(key, cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        if let Some(key) = self.key {
+            state.senders.remove(&key);
+        }
+
+        let message = self.message.take().expect("Message already sent");
+        let receiver = state.push(message);
+        drop(state);
+
+        if let Some(waker) = receiver {
+            waker.wake();
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, A: Actor> Drop for WaitingSend<'a, A> {
+    fn drop(&mut self) {
+        let key = match self.key {
+            Some(key) if self.message.is_some() => key,
+            _ => return,
+        };
+
+        let mut state = self.shared.lock();
+        let waiting = state.senders.remove(&key).is_some();
+
+        // If the send was woken to take a free slot but was cancelled before taking it,
+        // pass the slot on to the next waiting send.
+        let sender = if !waiting && !state.is_full() {
+            state.next_sender()
+        } else {
+            None
+        };
+        drop(state);
+
+        if let Some(waker) = sender {
+            waker.wake();
+        }
+    }
+}
+
+/// A message that was rejected by the mailbox, along with the reason it was rejected.
+pub(crate) struct Rejected<T> {
+    pub(crate) message: T,
+    pub(crate) reason: MessageErrorCause,
+}
+
+impl<T> Rejected<T> {
+    fn new(message: T, reason: MessageErrorCause) -> Self {
+        Self { message, reason }
+    }
+}
@@ -1,15 +1,175 @@
 //! Traits for defining actor messages.
 
-use crate::Actor;
+use crate::{Actor, Context, RequestError};
 use futures::future::BoxFuture;
+use std::{fmt, marker::PhantomData};
 
 pub trait Message: 'static + Sized + Send {
     type Actor: Actor;
     type Output: Sized + Send;
 
-    fn handle(self, actor: &mut Self::Actor) -> BoxFuture<'_, Self::Output>;
+    fn handle<'a>(
+        self,
+        actor: &'a mut Self::Actor,
+        ctx: &'a mut Context<Self::Actor>,
+    ) -> BoxFuture<'a, Self::Output>;
+
+    /// A human-readable name for the message type, e.g. for reporting [dead letters].
+    ///
+    /// Defaults to the name of the type. The `#[thespian::actor]` macro uses the name of
+    /// the actor type and handler method instead, e.g. `MyActor::add`.
+    ///
+    /// [dead letters]: struct.DeadLetter.html
+    fn type_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Handles the message using only a shared reference to the actor.
+    ///
+    /// Returns the message back if handling it requires exclusive access to the actor,
+    /// which is the default. The `#[thespian::actor]` macro implements this for message
+    /// handlers that take `&self` and don't take a [`Context`].
+    ///
+    /// [`Context`]: struct.Context.html
+    //
+    // NOTE: The bound is spelled `&Self::Actor: Send` rather than `Self::Actor: Sync` so
+    // that it isn't rejected as a trivially false bound when implementing this for a
+    // message whose actor type isn't `Sync`.
+    fn handle_shared<'a>(self, _actor: &'a Self::Actor) -> Result<BoxFuture<'a, Self::Output>, Self>
+    where
+        for<'x> &'x Self::Actor: Send,
+    {
+        Err(self)
+    }
+}
+
+/// Implemented by actors that can handle messages of type `M`.
+///
+/// Unlike [`Message`], which ties each message type to a single actor type, any number
+/// of actor types can implement `Handler` for the same message type. This allows
+/// messages to be sent through a [`Recipient`] or [`RequestRecipient`] without the
+/// sender needing to know the concrete type of the actor handling them.
+///
+/// `Handler` is only implemented for message types you define yourself. The message
+/// types generated by `#[thespian::actor]` are private to the macro's output, so the
+/// methods of an actor impl block can only be called through the actor's proxy.
+///
+/// [`Message`]: trait.Message.html
+/// [`Recipient`]: struct.Recipient.html
+/// [`RequestRecipient`]: struct.RequestRecipient.html
+pub trait Handler<M: 'static + Send>: Actor {
+    type Output: 'static + Send;
+
+    fn handle<'a>(
+        &'a mut self,
+        message: M,
+        ctx: &'a mut Context<Self>,
+    ) -> BoxFuture<'a, Self::Output>;
+}
+
+/// Adapts a message handled via [`Handler`] so that it can be sent as a [`Message`].
+///
+/// [`Handler`]: trait.Handler.html
+/// [`Message`]: trait.Message.html
+pub(crate) struct Handled<A, M> {
+    message: M,
+    _actor: PhantomData<fn() -> A>,
+}
+
+impl<A, M> Handled<A, M> {
+    pub(crate) fn new(message: M) -> Self {
+        Self {
+            message,
+            _actor: PhantomData,
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> M {
+        self.message
+    }
+}
+
+impl<A, M> Message for Handled<A, M>
+where
+    A: Handler<M>,
+    M: 'static + Send,
+{
+    type Actor = A;
+    type Output = A::Output;
+
+    fn handle<'a>(self, actor: &'a mut A, ctx: &'a mut Context<A>) -> BoxFuture<'a, A::Output> {
+        Handler::handle(actor, self.message, ctx)
+    }
+
+    fn type_name() -> &'static str {
+        std::any::type_name::<M>()
+    }
 }
 
 pub trait ErasedMessage<A: Actor>: Send {
-    fn handle(self: Box<Self>, actor: &mut A) -> BoxFuture<'_, ()>;
+    fn handle<'a>(self: Box<Self>, actor: &'a mut A, ctx: &'a mut Context<A>) -> BoxFuture<'a, ()>;
+
+    /// Handles the message using only a shared reference to the actor, or returns the
+    /// message back if it requires exclusive access.
+    fn handle_shared<'a>(
+        self: Box<Self>,
+        actor: &'a A,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<A>>>
+    where
+        for<'x> &'x A: Send;
+
+    /// Discards the message without handling it.
+    ///
+    /// If the message is a request, the requester is notified with the specified error.
+    fn reject(self: Box<Self>, _error: RequestError) {}
+
+    /// The name of the message type, as given by [`Message::type_name`].
+    ///
+    /// [`Message::type_name`]: trait.Message.html#method.type_name
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Shared access to an actor, used to handle read-only messages concurrently.
+///
+/// Returned from [`Actor::shared_access`]. Can only be created for actors that are
+/// `Sync`, since the read-only messages may be handled from different threads.
+///
+/// [`Actor::shared_access`]: trait.Actor.html#method.shared_access
+pub struct SharedAccess<'a, A: Actor> {
+    // NOTE: We hold onto a closure rather than the reference itself so that the `Sync`
+    // bound is only needed when creating the `SharedAccess`. This lets the stage use it
+    // without needing to know that the actor type is `Sync`.
+    #[allow(clippy::type_complexity)]
+    handle: Box<
+        dyn Fn(Box<dyn ErasedMessage<A>>) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<A>>>
+            + Send
+            + Sync
+            + 'a,
+    >,
+}
+
+impl<'a, A: Actor + Sync> SharedAccess<'a, A> {
+    pub fn new(actor: &'a A) -> Self {
+        Self {
+            handle: Box::new(move |message| message.handle_shared(actor)),
+        }
+    }
+}
+
+impl<'a, A: Actor> SharedAccess<'a, A> {
+    /// Starts handling `message` if it's read-only, otherwise returns it back.
+    pub(crate) fn handle(
+        &self,
+        message: Box<dyn ErasedMessage<A>>,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<A>>> {
+        (self.handle)(message)
+    }
+}
+
+impl<'a, A: Actor> fmt::Debug for SharedAccess<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedAccess").finish()
+    }
 }
@@ -0,0 +1,86 @@
+//! Support for watching other actors and being notified when they stop.
+
+use crate::{
+    context::Task, message::Handled, remote::JoinHandle, remote::RemoteInner, ActorId,
+    ErasedMessage, ExitReason, Handler,
+};
+use futures::{prelude::*, stream};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The message delivered to an actor when an actor it monitors has stopped.
+///
+/// Monitor another actor using [`Context::monitor`]. The monitoring actor must
+/// implement [`Handler<Terminated>`] in order to receive the notification.
+///
+/// # Examples
+///
+/// ```
+/// use futures::{future::BoxFuture, prelude::*};
+/// use std::collections::HashMap;
+/// use thespian::{Actor, ActorId, Context, Handler, MonitorError, Terminated};
+///
+/// #[derive(Default, Actor)]
+/// pub struct Sessions {
+///     names: HashMap<ActorId, String>,
+/// }
+///
+/// #[thespian::actor]
+/// impl Sessions {
+///     pub fn add(
+///         &mut self,
+///         id: ActorId,
+///         name: String,
+///         ctx: &mut Context<Self>,
+///     ) -> Result<(), MonitorError> {
+///         ctx.monitor(id)?;
+///         self.names.insert(id, name);
+///         Ok(())
+///     }
+/// }
+///
+/// impl Handler<Terminated> for Sessions {
+///     type Output = ();
+///
+///     fn handle(&mut self, terminated: Terminated, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+///         self.names.remove(&terminated.id);
+///         future::ready(()).boxed()
+///     }
+/// }
+/// ```
+///
+/// [`Context::monitor`]: struct.Context.html#method.monitor
+/// [`Handler<Terminated>`]: trait.Handler.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Terminated {
+    /// The ID of the actor that stopped.
+    pub id: ActorId,
+
+    /// Why the actor stopped.
+    pub reason: ExitReason,
+}
+
+/// Error returned when monitoring or linking to an actor fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum MonitorError {
+    /// No actor with the given ID exists, e.g. because it stopped and was dropped.
+    #[error("No actor exists with ID {0}")]
+    NotFound(ActorId),
+}
+
+/// Finds the remote for the actor with the given ID.
+pub(crate) fn find(id: ActorId) -> Result<Arc<RemoteInner>, MonitorError> {
+    crate::id::find(id).ok_or(MonitorError::NotFound(id))
+}
+
+/// Creates a task that notifies the actor once the watched actor has stopped.
+pub(crate) fn watch<A: Handler<Terminated>>(remote: Arc<RemoteInner>) -> Task<A> {
+    let id = remote.id();
+    let terminated = JoinHandle::new(remote).map(move |reason| {
+        Box::new(Handled::<A, Terminated>::new(Terminated { id, reason }))
+            as Box<dyn ErasedMessage<A>>
+    });
+
+    stream::once(terminated).boxed()
+}
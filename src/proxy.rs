@@ -1,26 +1,87 @@
-use crate::{envelope::*, message::*, Actor, MessageError};
-use derivative::Derivative;
-use futures::{
-    channel::{mpsc, oneshot},
-    prelude::*,
+use crate::{
+    blocking, envelope::*, mailbox::*, message::*, recipient::*, Actor, ActorId, MessageError,
+    RequestError, SendError,
 };
+use derivative::Derivative;
+use futures::{channel::oneshot, future::BoxFuture, prelude::*};
 use std::{
+    hash::{Hash, Hasher},
     mem,
     sync::{Arc, Weak},
 };
 
-pub(crate) type EnvelopeSender<A> = mpsc::Sender<Envelope<A>>;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+use crate::TimerHandle;
 
 pub trait ActorProxy: Sized + Clone {
     type Actor: Actor<Proxy = Self>;
 
     fn new(inner: ProxyFor<Self::Actor>) -> Self;
+
+    /// Returns the underlying [`ProxyFor`] for the actor.
+    ///
+    /// [`ProxyFor`]: struct.ProxyFor.html
+    fn inner(&self) -> &ProxyFor<Self::Actor>;
+
+    /// Returns the ID of the actor.
+    fn id(&self) -> ActorId {
+        self.inner().id()
+    }
+
+    /// Creates a [`Recipient`] for sending messages of type `M` to the actor.
+    ///
+    /// [`Recipient`]: struct.Recipient.html
+    fn recipient<M>(&self) -> Recipient<M>
+    where
+        M: 'static + Send,
+        Self::Actor: Handler<M>,
+    {
+        self.inner().recipient()
+    }
+
+    /// Creates a [`RequestRecipient`] for sending requests of type `M` to the actor.
+    ///
+    /// [`RequestRecipient`]: struct.RequestRecipient.html
+    fn request_recipient<M>(&self) -> RequestRecipient<M, <Self::Actor as Handler<M>>::Output>
+    where
+        M: 'static + Send,
+        Self::Actor: Handler<M>,
+    {
+        self.inner().request_recipient()
+    }
+
+    /// Sends a message to the actor once `duration` has elapsed, returning a handle that
+    /// can be used to cancel the timer.
+    ///
+    /// See [`ProxyFor::send_after`] for more details.
+    ///
+    /// [`ProxyFor::send_after`]: struct.ProxyFor.html#method.send_after
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    fn send_after<M>(&self, duration: std::time::Duration, message: M) -> TimerHandle
+    where
+        M: 'static + Send,
+        Self::Actor: Handler<M>,
+    {
+        self.inner()
+            .send_after(duration, Handled::<Self::Actor, M>::new(message))
+    }
 }
 
+/// Low-level proxy used to send messages to an actor of type `A`.
+///
+/// Sending a message only requires a shared reference to the proxy, so a single proxy
+/// can be shared between tasks, e.g. by wrapping it in an `Arc`, without needing to be
+/// cloned for each task.
+///
+/// Proxies compare equal and hash the same if they refer to the same actor, as
+/// identified by its [`ActorId`].
+///
+/// [`ActorId`]: struct.ActorId.html
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""), Clone(bound = ""))]
 pub struct ProxyFor<A: Actor> {
-    sink: EnvelopeSender<A>,
+    pub(crate) sink: MailboxSender<A>,
+    id: ActorId,
 
     // NOTE: We wrap the ref count in an `Option` in order to control the drop order.
     // On drop, we send a message to the stage, but we need to ensure that the ref
@@ -31,81 +92,372 @@ pub struct ProxyFor<A: Actor> {
 }
 
 impl<A: Actor> ProxyFor<A> {
-    pub(crate) fn new(sink: EnvelopeSender<A>) -> Self {
+    pub(crate) fn new(sink: MailboxSender<A>, id: ActorId) -> Self {
         Self {
             sink,
+            id,
             proxy_count: Some(Arc::new(())),
         }
     }
 
+    /// Returns the ID of the actor.
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
     /// Sends a message to an actor.
     ///
     /// If the actor is still running and there is space in its message queue, the
-    /// message will be enqueued synchronously. Otherwise, an error will be returned.
-    pub fn send_message<M: Message<Actor = A>>(&mut self, message: M) -> Result<(), MessageError> {
-        let erased_message = Box::new(message);
-        let envelope = Envelope::Message(erased_message);
-        self.sink.try_send(envelope).map_err(Into::into)
+    /// message will be enqueued synchronously. If the actor's mailbox is full, the
+    /// outcome depends on the mailbox's [`OverflowPolicy`]. Otherwise, an error will be
+    /// returned, handing back the message.
+    ///
+    /// [`OverflowPolicy`]: enum.OverflowPolicy.html
+    pub fn send_message<M: Message<Actor = A>>(&self, message: M) -> Result<(), SendError<M>> {
+        self.sink
+            .try_push(message, |message| Box::new(message))
+            .map_err(|rejected| self.send_error(rejected))
     }
 
     /// Sends a request to an actor, returning a future yielding the actor's response.
     ///
     /// If the actor has stopped or its message queue is full, this method will return
-    /// an error synchronously. Otherwise, the message will be queued and the returned
-    /// future will resolve to the actor's response.
+    /// an error synchronously (subject to the mailbox's [`OverflowPolicy`]), handing back
+    /// the request. Otherwise, the message will be queued and the returned future will
+    /// resolve to the actor's response.
+    ///
+    /// If the actor panics while handling the message, or stops before handling it, the
+    /// returned future will resolve to a [`RequestError`] instead.
     ///
-    /// If the actor panics while handling the message, the panic will be propagated to
-    /// any code awaiting the response.
+    /// [`OverflowPolicy`]: enum.OverflowPolicy.html
+    /// [`RequestError`]: enum.RequestError.html
     pub fn send_request<R: Message<Actor = A>>(
-        &mut self,
+        &self,
         message: R,
-    ) -> Result<impl Future<Output = R::Output>, MessageError> {
+    ) -> Result<impl Future<Output = Result<R::Output, RequestError>>, SendError<R>> {
+        // NOTE: The request is only wrapped in an envelope once it has been accepted, so
+        // that it can be handed back as-is if it's rejected.
         let (result_sender, result) = oneshot::channel();
-        let erased_message = Box::new(RequestEnvelope {
-            message,
-            result_sender,
-        });
-        let envelope = Envelope::Message(erased_message);
-        self.sink.try_send(envelope)?;
+        self.sink
+            .try_push(message, |message| {
+                Box::new(RequestEnvelope {
+                    message,
+                    result_sender,
+                })
+            })
+            .map_err(|rejected| self.send_error(rejected))?;
+        Ok(response(result))
+    }
+
+    /// Sends a request to an actor, returning a future yielding the actor's response or
+    /// an error if the actor doesn't respond within `duration`.
+    ///
+    /// See [`send_request`] for more details. If the timeout elapses the returned future
+    /// resolves to [`RequestError::TimedOut`], and the actor's response will be
+    /// discarded if it responds later.
+    ///
+    /// [`send_request`]: #method.send_request
+    /// [`RequestError::TimedOut`]: enum.RequestError.html#variant.TimedOut
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn send_request_timeout<R: Message<Actor = A>>(
+        &self,
+        message: R,
+        duration: std::time::Duration,
+    ) -> Result<impl Future<Output = Result<R::Output, RequestError>>, SendError<R>> {
+        let response = self.send_request(message)?;
+        Ok(timeout(duration, response))
+    }
+
+    /// Sends a message to an actor, waiting for space in the actor's mailbox.
+    ///
+    /// Unlike [`send_message`], this doesn't fail if the actor's mailbox is full.
+    /// Instead, the returned future waits until there is space in the mailbox,
+    /// regardless of the mailbox's overflow policy. This allows producers to slow down
+    /// to match the rate at which the actor handles messages.
+    ///
+    /// The returned future resolves to an error if the actor stops.
+    ///
+    /// [`send_message`]: #method.send_message
+    pub async fn send_message_wait<M: Message<Actor = A>>(
+        &self,
+        message: M,
+    ) -> Result<(), MessageError> {
+        self.sink.send(Box::new(message)).await
+    }
+
+    /// Sends a request to an actor, waiting for space in the actor's mailbox.
+    ///
+    /// Once the request has been queued, the returned future resolves to a second
+    /// future yielding the actor's response, as with [`send_request`]. See
+    /// [`send_message_wait`] for more details on waiting for mailbox space.
+    ///
+    /// [`send_request`]: #method.send_request
+    /// [`send_message_wait`]: #method.send_message_wait
+    pub async fn send_request_wait<R: Message<Actor = A>>(
+        &self,
+        message: R,
+    ) -> Result<impl Future<Output = Result<R::Output, RequestError>>, MessageError> {
+        let (envelope, result) = request_envelope(message);
+        self.sink.send(envelope).await?;
+        Ok(response(result))
+    }
+
+    /// Sends a message to an actor from synchronous code, blocking the current thread
+    /// until there is space in the actor's mailbox.
+    ///
+    /// This is the blocking version of [`send_message_wait`], for use from threads that
+    /// aren't running an async executor, e.g. worker threads or FFI callbacks. It
+    /// doesn't require a runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an actor, e.g. in a message handler, since blocking
+    /// the thread keeps the actor from running and could deadlock. Note that blocking
+    /// the thread from other async code is also likely to stall the executor.
+    ///
+    /// [`send_message_wait`]: #method.send_message_wait
+    pub fn send_message_blocking<M: Message<Actor = A>>(
+        &self,
+        message: M,
+    ) -> Result<(), MessageError> {
+        blocking::block_on(self.send_message_wait(message))
+    }
+
+    /// Sends a request to an actor from synchronous code, blocking the current thread
+    /// until the actor responds.
+    ///
+    /// This waits for space in the actor's mailbox, as with [`send_request_wait`], and
+    /// then waits for the actor's response. It doesn't require a runtime. If the actor
+    /// has already stopped, the request is reported as a [dead letter] and this returns
+    /// [`RequestError::ActorStopped`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an actor, e.g. in a message handler, since blocking
+    /// the thread keeps the actor from running and could deadlock. Note that blocking
+    /// the thread from other async code is also likely to stall the executor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thespian::Actor;
+    ///
+    /// #[derive(Default, Actor)]
+    /// pub struct Counter {
+    ///     value: usize,
+    /// }
+    ///
+    /// #[thespian::actor]
+    /// impl Counter {
+    ///     pub fn increment(&mut self) -> usize {
+    ///         self.value += 1;
+    ///         self.value
+    ///     }
+    /// }
+    ///
+    /// let stage = Counter::default().into_stage();
+    /// let counter = stage.proxy();
+    /// std::thread::spawn(move || futures::executor::block_on(stage.run()));
+    ///
+    /// assert_eq!(1, counter.increment_blocking().unwrap());
+    /// ```
+    ///
+    /// [`send_request_wait`]: #method.send_request_wait
+    /// [dead letter]: struct.DeadLetter.html
+    /// [`RequestError::ActorStopped`]: enum.RequestError.html#variant.ActorStopped
+    pub fn send_request_blocking<R: Message<Actor = A>>(
+        &self,
+        message: R,
+    ) -> Result<R::Output, RequestError> {
+        blocking::block_on(async move {
+            let response = self
+                .send_request_wait(message)
+                .await
+                .map_err(|_| RequestError::ActorStopped)?;
+            response.await
+        })
+    }
 
-        // Message was successfully enqueued. Return a future that awaits the message
-        // response and panics if the actor failed to one, since the only case where an
-        // actor wouldn't send a response is if it panics while handling the request.
-        Ok(async { result.await.expect("Actor panicked while handling message") })
+    /// Sends a message to an actor once `duration` has elapsed.
+    ///
+    /// The message is sent as with [`send_message`] when the timer fires. If the message
+    /// can't be sent at that point, e.g. because the actor has stopped, it is reported as
+    /// a [dead letter]. The pending timer doesn't count as a proxy to the actor, so it
+    /// won't keep the actor alive.
+    ///
+    /// [`send_message`]: #method.send_message
+    /// [dead letter]: struct.DeadLetter.html
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn send_after<M: Message<Actor = A>>(
+        &self,
+        duration: std::time::Duration,
+        message: M,
+    ) -> TimerHandle {
+        let (timer, registration) = TimerHandle::new();
+        let sink = self.sink.clone();
+        let send = async move {
+            crate::runtime::delay(duration).await;
+            let _ = sink.try_send(Box::new(message));
+        };
+        crate::runtime::spawn(future::Abortable::new(send, registration));
+        timer
+    }
+
+    /// Creates a [`Recipient`] for sending messages of type `M` to the actor.
+    ///
+    /// [`Recipient`]: struct.Recipient.html
+    pub fn recipient<M>(&self) -> Recipient<M>
+    where
+        M: 'static + Send,
+        A: Handler<M>,
+    {
+        Recipient::new(self.clone())
+    }
+
+    /// Creates a [`RequestRecipient`] for sending requests of type `M` to the actor.
+    ///
+    /// [`RequestRecipient`]: struct.RequestRecipient.html
+    pub fn request_recipient<M>(&self) -> RequestRecipient<M, A::Output>
+    where
+        M: 'static + Send,
+        A: Handler<M>,
+    {
+        RequestRecipient::new(self.clone())
+    }
+
+    /// Creates the error for a message rejected by the actor's mailbox, which reports the
+    /// message as a dead letter unless the caller takes it back.
+    fn send_error<M: Message<Actor = A>>(&self, rejected: Rejected<M>) -> SendError<M> {
+        let Rejected { message, reason } = rejected;
+        let sink = self.sink.clone();
+        let dead_letter = reason.clone().into();
+        SendError::new(message, reason, move |message| {
+            sink.report(Box::new(message), dead_letter)
+        })
     }
 
     pub(crate) fn count(&self) -> usize {
         Arc::strong_count(self.proxy_count.as_ref().unwrap())
     }
 
+    /// Returns a weak reference to the proxy count, which can be used to check if any
+    /// proxies to the actor still exist.
+    pub(crate) fn weak_count_ref(&self) -> Weak<()> {
+        Arc::downgrade(self.proxy_count.as_ref().unwrap())
+    }
+
     pub(crate) fn downgrade(&self) -> WeakProxyFor<A> {
         WeakProxyFor {
             sink: self.sink.clone(),
+            id: self.id,
             proxy_count: Arc::downgrade(self.proxy_count.as_ref().unwrap()),
         }
     }
 }
 
+/// Waits for the response to a request, failing if the actor doesn't respond within
+/// `duration`.
+///
+/// This can be used with the request methods on generated proxy types, e.g.
+/// `thespian::timeout(duration, proxy.value()?).await`. If the timeout elapses the
+/// returned future resolves to [`RequestError::TimedOut`].
+///
+/// [`RequestError::TimedOut`]: enum.RequestError.html#variant.TimedOut
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub async fn timeout<T, F>(duration: std::time::Duration, response: F) -> Result<T, RequestError>
+where
+    F: Future<Output = Result<T, RequestError>>,
+{
+    futures::pin_mut!(response);
+    let delay = crate::runtime::delay(duration);
+    futures::pin_mut!(delay);
+
+    match future::select(response, delay).await {
+        future::Either::Left((result, _)) => result,
+        future::Either::Right(_) => Err(RequestError::TimedOut),
+    }
+}
+
+/// A boxed future yielding an actor's response to a request.
+///
+/// This is returned by the methods of proxy-side interface traits generated by
+/// `#[thespian::interface]`, since trait methods can't return `impl Future`.
+pub type ResponseFuture<T> = BoxFuture<'static, Result<T, RequestError>>;
+
+/// Flattens the response to a request whose handler responds with a [`ResponseFuture`],
+/// e.g. one returned by [`Context::await_then`], so that the requester gets the final
+/// value directly.
+///
+/// Used by the proxy methods generated by `#[thespian::actor]`.
+///
+/// [`ResponseFuture`]: type.ResponseFuture.html
+/// [`Context::await_then`]: struct.Context.html#method.await_then
+#[doc(hidden)]
+pub async fn flatten_response<T, F>(response: F) -> Result<T, RequestError>
+where
+    F: Future<Output = Result<ResponseFuture<T>, RequestError>>,
+{
+    response.await?.await
+}
+
+pub(crate) type ResponseReceiver<T> = oneshot::Receiver<Result<T, RequestError>>;
+
+/// Wraps a request in an envelope, returning the envelope and the receiver for the
+/// actor's response.
+pub(crate) fn request_envelope<R: Message>(
+    message: R,
+) -> (
+    Box<dyn ErasedMessage<R::Actor>>,
+    ResponseReceiver<R::Output>,
+) {
+    let (result_sender, result) = oneshot::channel();
+    let envelope = Box::new(RequestEnvelope {
+        message,
+        result_sender,
+    });
+
+    (envelope, result)
+}
+
+/// Waits for the actor's response to a request.
+///
+/// If the actor panics while handling the request it will send back the error, so the
+/// only case where the response is cancelled is if the actor stopped without ever
+/// handling the request.
+pub(crate) async fn response<T>(result: ResponseReceiver<T>) -> Result<T, RequestError> {
+    result.await.unwrap_or(Err(RequestError::ActorStopped))
+}
+
+impl<A: Actor> PartialEq for ProxyFor<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<A: Actor> Eq for ProxyFor<A> {}
+
+impl<A: Actor> Hash for ProxyFor<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 impl<A: Actor> Drop for ProxyFor<A> {
     fn drop(&mut self) {
         // Manually drop the inner ref count in order to ensure the count has decreased
         // *before* the stage receives the drop message.
         mem::drop(self.proxy_count.take());
 
-        // Send the drop message so that the stage can stop itself if there are no
-        // proxies left.
-        //
-        // NOTE: We don't care if the message send fails here in the case that the buffer
-        // is full. If that happens, the stage will check the proxy count after processing
-        // the next message to see if there are any proxies left.
-        let _ = self.sink.try_send(Envelope::ProxyDropped);
+        // Notify the stage so that it can stop itself if there are no proxies left.
+        self.sink.notify_proxy_dropped();
     }
 }
 
 #[derive(Derivative)]
-#[derivative(Debug(bound = ""))]
+#[derivative(Debug(bound = ""), Clone(bound = ""))]
 pub(crate) struct WeakProxyFor<A: Actor> {
-    sink: EnvelopeSender<A>,
+    sink: MailboxSender<A>,
+    id: ActorId,
     proxy_count: Weak<()>,
 }
 
@@ -113,6 +465,7 @@ impl<A: Actor> WeakProxyFor<A> {
     pub(crate) fn upgrade(&self) -> Option<ProxyFor<A>> {
         self.proxy_count.upgrade().map(|proxy_count| ProxyFor {
             sink: self.sink.clone(),
+            id: self.id,
             proxy_count: Some(proxy_count),
         })
     }
@@ -0,0 +1,215 @@
+//! Type-erased handles for sending a single message type to any actor that handles it.
+
+use crate::{message::*, proxy::*, MessageError, RequestError, SendError};
+use futures::{future::BoxFuture, prelude::*};
+use std::{fmt, sync::Arc};
+
+/// A handle for sending messages of type `M` to an actor, without depending on the
+/// actor's concrete type.
+///
+/// A `Recipient` can be created from the proxy of any actor that implements
+/// [`Handler<M>`], using [`ActorProxy::recipient`]. Any output from the handler is
+/// discarded; use a [`RequestRecipient`] to get the actor's response. `M` is a message
+/// type you define yourself, not one generated by `#[thespian::actor]`.
+///
+/// As with a proxy, a `Recipient` keeps the actor running as long as it exists.
+///
+/// # Examples
+///
+/// ```
+/// use futures::{future::BoxFuture, prelude::*};
+/// use thespian::{Actor, ActorProxy, Context, Handler, Recipient};
+///
+/// pub struct LogLine(String);
+///
+/// #[derive(Default, Actor)]
+/// pub struct Console;
+///
+/// #[thespian::actor]
+/// impl Console {}
+///
+/// impl Handler<LogLine> for Console {
+///     type Output = ();
+///
+///     fn handle(&mut self, line: LogLine, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+///         println!("{}", line.0);
+///         future::ready(()).boxed()
+///     }
+/// }
+///
+/// let stage = Console.into_stage();
+/// let log: Recipient<LogLine> = stage.proxy().recipient();
+/// log.send_message(LogLine("Hello, world!".into())).unwrap();
+/// ```
+///
+/// [`Handler<M>`]: trait.Handler.html
+/// [`ActorProxy::recipient`]: trait.ActorProxy.html#method.recipient
+/// [`RequestRecipient`]: struct.RequestRecipient.html
+pub struct Recipient<M> {
+    sink: Arc<dyn MessageSink<M>>,
+}
+
+impl<M: 'static + Send> Recipient<M> {
+    pub(crate) fn new<A: Handler<M>>(proxy: ProxyFor<A>) -> Self {
+        Self {
+            sink: Arc::new(proxy),
+        }
+    }
+
+    /// Sends a message to the actor.
+    ///
+    /// See [`ProxyFor::send_message`] for details on how the message is queued.
+    ///
+    /// [`ProxyFor::send_message`]: struct.ProxyFor.html#method.send_message
+    pub fn send_message(&self, message: M) -> Result<(), SendError<M>> {
+        self.sink.send_message(message)
+    }
+
+    /// Sends a message to the actor, waiting for space in the actor's mailbox.
+    ///
+    /// See [`ProxyFor::send_message_wait`] for more details.
+    ///
+    /// [`ProxyFor::send_message_wait`]: struct.ProxyFor.html#method.send_message_wait
+    pub async fn send_message_wait(&self, message: M) -> Result<(), MessageError> {
+        self.sink.send_message_wait(message).await
+    }
+}
+
+impl<M> Clone for Recipient<M> {
+    fn clone(&self) -> Self {
+        Self {
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+impl<M> fmt::Debug for Recipient<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recipient")
+            .field("message", &std::any::type_name::<M>())
+            .finish()
+    }
+}
+
+/// A handle for sending requests of type `M` to an actor, without depending on the
+/// actor's concrete type.
+///
+/// A `RequestRecipient` can be created from the proxy of any actor that implements
+/// [`Handler<M>`] with `Output = O`, using [`ActorProxy::request_recipient`].
+///
+/// As with a proxy, a `RequestRecipient` keeps the actor running as long as it exists.
+///
+/// [`Handler<M>`]: trait.Handler.html
+/// [`ActorProxy::request_recipient`]: trait.ActorProxy.html#method.request_recipient
+pub struct RequestRecipient<M, O> {
+    sink: Arc<dyn RequestSink<M, O>>,
+}
+
+impl<M: 'static + Send, O: 'static + Send> RequestRecipient<M, O> {
+    pub(crate) fn new<A: Handler<M, Output = O>>(proxy: ProxyFor<A>) -> Self {
+        Self {
+            sink: Arc::new(proxy),
+        }
+    }
+
+    /// Sends a request to the actor, returning a future yielding the actor's response.
+    ///
+    /// See [`ProxyFor::send_request`] for more details.
+    ///
+    /// [`ProxyFor::send_request`]: struct.ProxyFor.html#method.send_request
+    pub fn send_request(
+        &self,
+        message: M,
+    ) -> Result<impl Future<Output = Result<O, RequestError>>, SendError<M>> {
+        self.sink.send_request(message)
+    }
+
+    /// Sends a request to the actor, waiting for space in the actor's mailbox.
+    ///
+    /// See [`ProxyFor::send_request_wait`] for more details.
+    ///
+    /// [`ProxyFor::send_request_wait`]: struct.ProxyFor.html#method.send_request_wait
+    pub async fn send_request_wait(
+        &self,
+        message: M,
+    ) -> Result<impl Future<Output = Result<O, RequestError>>, MessageError> {
+        self.sink.send_request_wait(message).await
+    }
+}
+
+impl<M, O> Clone for RequestRecipient<M, O> {
+    fn clone(&self) -> Self {
+        Self {
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+impl<M, O> fmt::Debug for RequestRecipient<M, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestRecipient")
+            .field("message", &std::any::type_name::<M>())
+            .field("output", &std::any::type_name::<O>())
+            .finish()
+    }
+}
+
+/// Object-safe interface for sending messages of type `M` to an actor.
+trait MessageSink<M>: Send + Sync {
+    fn send_message(&self, message: M) -> Result<(), SendError<M>>;
+
+    fn send_message_wait(&self, message: M) -> BoxFuture<'_, Result<(), MessageError>>;
+}
+
+impl<A, M> MessageSink<M> for ProxyFor<A>
+where
+    A: Handler<M>,
+    M: 'static + Send,
+{
+    fn send_message(&self, message: M) -> Result<(), SendError<M>> {
+        ProxyFor::send_message(self, Handled::<A, M>::new(message))
+            .map_err(|error| error.map_message(Handled::into_inner, Handled::new))
+    }
+
+    fn send_message_wait(&self, message: M) -> BoxFuture<'_, Result<(), MessageError>> {
+        self.sink
+            .send(Box::new(Handled::<A, M>::new(message)))
+            .boxed()
+    }
+}
+
+/// Object-safe interface for sending requests of type `M` to an actor.
+trait RequestSink<M, O>: Send + Sync {
+    fn send_request(&self, message: M) -> Result<ResponseFuture<O>, SendError<M>>;
+
+    fn send_request_wait(
+        &self,
+        message: M,
+    ) -> BoxFuture<'_, Result<ResponseFuture<O>, MessageError>>;
+}
+
+impl<A, M, O> RequestSink<M, O> for ProxyFor<A>
+where
+    A: Handler<M, Output = O>,
+    M: 'static + Send,
+    O: 'static + Send,
+{
+    fn send_request(&self, message: M) -> Result<ResponseFuture<O>, SendError<M>> {
+        match ProxyFor::send_request(self, Handled::<A, M>::new(message)) {
+            Ok(response) => Ok(response.boxed()),
+            Err(error) => Err(error.map_message(Handled::into_inner, Handled::new)),
+        }
+    }
+
+    fn send_request_wait(
+        &self,
+        message: M,
+    ) -> BoxFuture<'_, Result<ResponseFuture<O>, MessageError>> {
+        let (envelope, result) = request_envelope(Handled::<A, M>::new(message));
+        async move {
+            self.sink.send(envelope).await?;
+            Ok(response(result).boxed())
+        }
+        .boxed()
+    }
+}
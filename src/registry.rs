@@ -0,0 +1,142 @@
+//! Global registry for looking up actors by name.
+//!
+//! The registry only holds weak references to the actors registered in it, so
+//! registering an actor doesn't keep it running once all other proxies have been
+//! dropped.
+
+use crate::{
+    proxy::{ProxyFor, WeakProxyFor},
+    remote::RemoteInner,
+    stage::ActorState,
+    Actor, ActorProxy,
+};
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, OnceLock, Weak},
+};
+use thiserror::Error;
+
+/// Looks up the actor registered under `name`.
+///
+/// Returns `None` if no running actor is registered under `name`, or if the actor
+/// registered under `name` isn't of type `A`.
+///
+/// Actors are registered when they are created, using [`StageBuilder::name`] or
+/// [`Actor::spawn_named`], and are automatically removed from the registry once
+/// they stop.
+///
+/// # Examples
+///
+/// ```
+/// use thespian::{Actor, StageBuilder};
+///
+/// #[derive(Actor)]
+/// pub struct MyActor;
+///
+/// #[thespian::actor]
+/// impl MyActor {}
+///
+/// let (builder, _) = StageBuilder::new();
+/// let stage = builder.name("my-actor").unwrap().finish(MyActor);
+///
+/// let proxy = thespian::lookup::<MyActor>("my-actor");
+/// assert!(proxy.is_some());
+/// ```
+///
+/// [`StageBuilder::name`]: struct.StageBuilder.html#method.name
+/// [`Actor::spawn_named`]: trait.Actor.html#method.spawn_named
+pub fn lookup<A: Actor>(name: &str) -> Option<A::Proxy> {
+    let mut entries = entries();
+    let entry = entries.get(name)?;
+
+    // Clean up the entry if the actor stopped without deregistering itself, e.g.
+    // because its stage was dropped without being run.
+    if !entry.is_live() {
+        entries.remove(name);
+        return None;
+    }
+
+    entry
+        .proxy
+        .downcast_ref::<WeakProxyFor<A>>()
+        .and_then(WeakProxyFor::upgrade)
+        .map(A::Proxy::new)
+}
+
+/// Error returned when registering an actor fails.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum RegistryError {
+    /// Another running actor is already registered under the name.
+    #[error("An actor is already registered with the name {0:?}")]
+    NameTaken(String),
+}
+
+/// Registers the actor under `name`.
+pub(crate) fn register<A: Actor>(
+    name: &str,
+    proxy: &ProxyFor<A>,
+    remote: &Arc<RemoteInner>,
+) -> Result<(), RegistryError> {
+    let mut entries = entries();
+    if entries.get(name).is_some_and(Entry::is_live) {
+        return Err(RegistryError::NameTaken(name.into()));
+    }
+
+    entries.insert(
+        name.into(),
+        Entry {
+            proxy: Box::new(proxy.downgrade()),
+            proxy_count: proxy.weak_count_ref(),
+            remote: Arc::downgrade(remote),
+        },
+    );
+
+    Ok(())
+}
+
+/// Removes the actor registered under `name`, if it is the actor for `remote`.
+///
+/// The name may have already been reused by another actor if the entry had gone
+/// stale, so we check that the entry belongs to the stopped actor before removing it.
+pub(crate) fn deregister(name: &str, remote: &RemoteInner) {
+    let mut entries = entries();
+    if let Some(entry) = entries.get(name) {
+        if std::ptr::eq(entry.remote.as_ptr(), remote) {
+            entries.remove(name);
+        }
+    }
+}
+
+struct Entry {
+    /// The `WeakProxyFor<A>` for the registered actor.
+    proxy: Box<dyn Any + Send + Sync>,
+
+    /// Used to check if the actor can still receive messages without needing to know
+    /// the concrete type of the proxy.
+    proxy_count: Weak<()>,
+
+    remote: Weak<RemoteInner>,
+}
+
+impl Entry {
+    fn is_live(&self) -> bool {
+        if self.proxy_count.strong_count() == 0 {
+            return false;
+        }
+
+        match self.remote.upgrade() {
+            Some(remote) => !matches!(remote.state(), ActorState::Stopped | ActorState::Failed),
+            None => false,
+        }
+    }
+}
+
+fn entries() -> MutexGuard<'static, HashMap<String, Entry>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+    REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .expect("Registry lock poisoned")
+}
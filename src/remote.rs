@@ -1,18 +1,29 @@
 use crate::{
+    id::{self, ActorId},
     proxy::{ProxyFor, WeakProxyFor},
-    stage::ActorState,
+    registry,
+    stage::{ActorState, ExitReason},
     Actor, ActorProxy,
 };
+use derivative::Derivative;
+use futures::{
+    prelude::*,
+    task::{AtomicWaker, Context, Poll, Waker},
+};
 use std::{
+    collections::HashMap,
     convert::TryInto,
+    mem,
+    pin::Pin,
     sync::{
         atomic::{AtomicU8, Ordering},
-        Arc,
+        Arc, Mutex, Weak,
     },
 };
 
 /// Remote controller for an actor to manage its own state.
-#[derive(Debug)]
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Clone(bound = ""))]
 pub struct Remote<A: Actor> {
     inner: Arc<RemoteInner>,
     proxy: WeakProxyFor<A>,
@@ -46,22 +57,27 @@ impl<A: Actor> Remote<A> {
             let state = self.inner.state();
             match state {
                 ActorState::Running => {
-                    let result = self.inner.state.compare_and_swap(
+                    let result = self.inner.state.compare_exchange(
                         state.into(),
                         ActorState::Stopping.into(),
                         Ordering::SeqCst,
+                        Ordering::SeqCst,
                     );
 
-                    if result != ActorState::Stopping.into() {
+                    if result.is_err() {
                         continue;
                     }
+
+                    // Wake the stage in case it's waiting for a message.
+                    self.inner.waker.wake();
+                    return Ok(());
                 }
 
                 ActorState::Building | ActorState::Built => {
                     return Err(StopError);
                 }
 
-                ActorState::Stopping | ActorState::Stopped => {
+                ActorState::Stopping | ActorState::Stopped | ActorState::Failed => {
                     return Ok(());
                 }
             }
@@ -71,23 +87,261 @@ impl<A: Actor> Remote<A> {
     pub fn state(&self) -> ActorState {
         self.inner.state()
     }
+
+    /// Returns the ID of the actor.
+    pub fn id(&self) -> ActorId {
+        self.inner.id()
+    }
+
+    /// Returns a future that resolves once the actor has finished running.
+    pub fn stopped(&self) -> JoinHandle {
+        JoinHandle::new(self.inner.clone())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct StopError;
 
+/// A future that resolves once an actor has finished running.
+///
+/// Resolves to the reason the actor stopped. A `JoinHandle` can be obtained from
+/// [`Remote::stopped`], [`Stage::join_handle`], or by spawning the actor with
+/// [`Actor::spawn_with_handle`].
+///
+/// [`Remote::stopped`]: struct.Remote.html#method.stopped
+/// [`Stage::join_handle`]: struct.Stage.html#method.join_handle
+/// [`Actor::spawn_with_handle`]: trait.Actor.html#method.spawn_with_handle
+#[derive(Debug)]
+pub struct JoinHandle {
+    inner: Arc<RemoteInner>,
+
+    /// The key for this handle's waker in the actor's list of waiters, once the handle
+    /// has been polled.
+    key: Option<usize>,
+}
+
+impl JoinHandle {
+    pub(crate) fn new(inner: Arc<RemoteInner>) -> Self {
+        Self { inner, key: None }
+    }
+}
+
+impl Clone for JoinHandle {
+    fn clone(&self) -> Self {
+        // NOTE: The clone registers its own waker when it's polled, rather than sharing
+        // the original handle's entry.
+        Self::new(self.inner.clone())
+    }
+}
+
+impl Future for JoinHandle {
+    type Output = ExitReason;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut exit = this.inner.exit.lock().expect("Exit lock poisoned");
+        if let Some(reason) = &exit.reason {
+            return Poll::Ready(reason.clone());
+        }
+
+        // Replace the waker from any previous poll rather than adding a new one, so that
+        // repeatedly polling the handle doesn't accumulate wakers.
+        let key = *this.key.get_or_insert_with(|| {
+            exit.next_key += 1;
+            exit.next_key
+        });
+        exit.waiters.insert(key, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for JoinHandle {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.inner
+                .exit
+                .lock()
+                .expect("Exit lock poisoned")
+                .waiters
+                .remove(&key);
+        }
+    }
+}
+
+/// Finishes the actor with [`ExitReason::Dropped`] if it's dropped before the actor has
+/// finished running.
+///
+/// This ensures that any `JoinHandle`s for the actor resolve even if its stage is
+/// dropped without being run, or the future running it is cancelled.
+///
+/// [`ExitReason::Dropped`]: enum.ExitReason.html#variant.Dropped
+#[derive(Debug)]
+pub(crate) struct ExitGuard {
+    inner: Arc<RemoteInner>,
+}
+
+impl ExitGuard {
+    pub(crate) fn new(inner: Arc<RemoteInner>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        if !self.inner.is_finished() {
+            self.inner.finish(ExitReason::Dropped);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct RemoteInner {
+    id: ActorId,
     state: AtomicU8,
+    exit: Mutex<Exit>,
+
+    /// Actors linked to this one, which are stopped when this actor finishes.
+    links: Mutex<Vec<Weak<RemoteInner>>>,
+
+    /// The name the actor is registered under, if any.
+    name: Mutex<Option<String>>,
+
+    /// Used to wake the stage when the actor is stopped.
+    waker: AtomicWaker,
+}
+
+/// Tracks why the actor stopped, and any tasks waiting for it to stop.
+#[derive(Debug, Default)]
+struct Exit {
+    reason: Option<ExitReason>,
+
+    /// Wakers for any `JoinHandle`s waiting for the actor to stop, keyed by handle.
+    waiters: HashMap<usize, Waker>,
+    next_key: usize,
 }
 
 impl RemoteInner {
     pub(crate) fn new(state: ActorState) -> Self {
         Self {
+            id: ActorId::next(),
             state: AtomicU8::new(state.into()),
+            exit: Default::default(),
+            links: Default::default(),
+            name: Default::default(),
+            waker: AtomicWaker::new(),
         }
     }
 
+    pub(crate) fn id(&self) -> ActorId {
+        self.id
+    }
+
+    pub(crate) fn register_waker(&self, waker: &Waker) {
+        self.waker.register(waker);
+    }
+
+    /// Marks that the actor has started running.
+    ///
+    /// If the actor was asked to stop before it started running it is left in the
+    /// `Stopping` state, so that it stops as soon as it has started.
+    pub(crate) fn start(&self) {
+        let _ = self
+            .state
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |state| {
+                match state.try_into() {
+                    Ok(ActorState::Stopping) => None,
+                    _ => Some(ActorState::Running.into()),
+                }
+            });
+    }
+
+    /// Asks the actor to stop, even if it hasn't started running yet.
+    ///
+    /// Unlike [`Remote::stop`], this doesn't fail if the actor is still being built,
+    /// since the actor will see that it has been asked to stop once it starts running.
+    ///
+    /// [`Remote::stop`]: struct.Remote.html#method.stop
+    pub(crate) fn request_stop(&self) {
+        let _ = self
+            .state
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |state| {
+                match state.try_into() {
+                    Ok(ActorState::Building) | Ok(ActorState::Built) | Ok(ActorState::Running) => {
+                        Some(ActorState::Stopping.into())
+                    }
+                    _ => None,
+                }
+            });
+
+        // Wake the stage in case it's waiting for a message.
+        self.waker.wake();
+    }
+
+    pub(crate) fn name(&self) -> Option<String> {
+        self.name.lock().expect("Name lock poisoned").clone()
+    }
+
+    pub(crate) fn set_name(&self, name: String) {
+        *self.name.lock().expect("Name lock poisoned") = Some(name);
+    }
+
+    /// Links this actor to `other`, so that each is stopped when the other finishes.
+    ///
+    /// If either actor has already finished, the other is asked to stop immediately.
+    pub(crate) fn link(self: &Arc<Self>, other: &Arc<RemoteInner>) {
+        for (from, to) in [(self, other), (other, self)] {
+            // NOTE: The link is added before checking whether `from` has finished, and
+            // `finish` records the exit reason before taking the links, so `to` is
+            // always stopped even if `from` finishes concurrently.
+            from.links
+                .lock()
+                .expect("Links lock poisoned")
+                .push(Arc::downgrade(to));
+
+            if from.is_finished() {
+                to.request_stop();
+            }
+        }
+    }
+
+    /// Marks that the actor has finished running, waking any tasks waiting for it to
+    /// stop and stopping any linked actors.
+    ///
+    /// If the actor was registered by name it is removed from the registry before any
+    /// waiting tasks are woken, so that the name is free to be reused by then.
+    pub(crate) fn finish(&self, reason: ExitReason) {
+        self.set_state(reason.state());
+
+        if let Some(name) = self.name.lock().expect("Name lock poisoned").take() {
+            registry::deregister(&name, self);
+        }
+
+        let waiters = {
+            let mut exit = self.exit.lock().expect("Exit lock poisoned");
+            exit.reason = Some(reason);
+            mem::take(&mut exit.waiters)
+        };
+
+        for waker in waiters.into_values() {
+            waker.wake();
+        }
+
+        let links = mem::take(&mut *self.links.lock().expect("Links lock poisoned"));
+        for linked in links.iter().filter_map(Weak::upgrade) {
+            linked.request_stop();
+        }
+    }
+
+    /// Returns whether the actor has finished running, i.e. whether `finish` has been
+    /// called.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.exit
+            .lock()
+            .expect("Exit lock poisoned")
+            .reason
+            .is_some()
+    }
+
     pub(crate) fn set_state(&self, state: ActorState) -> ActorState {
         self.state
             .swap(state.into(), Ordering::SeqCst)
@@ -102,3 +356,9 @@ impl RemoteInner {
             .expect("Failed to convert raw actor state")
     }
 }
+
+impl Drop for RemoteInner {
+    fn drop(&mut self) {
+        id::deregister(self.id);
+    }
+}
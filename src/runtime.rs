@@ -1,4 +1,5 @@
 use futures::{Future, FutureExt};
+use std::time::Duration;
 
 #[cfg(feature = "tokio")]
 pub fn spawn<F>(future: F)
@@ -8,6 +9,11 @@ where
     tokio::spawn(future.map(|_| {}));
 }
 
+#[cfg(feature = "tokio")]
+pub async fn delay(duration: Duration) {
+    tokio::time::delay_for(duration).await;
+}
+
 #[cfg(feature = "async-std")]
 pub fn spawn<F>(future: F)
 where
@@ -15,3 +21,8 @@ where
 {
     async_std::task::spawn(future.map(|_| {}));
 }
+
+#[cfg(feature = "async-std")]
+pub async fn delay(duration: Duration) {
+    async_std::task::sleep(duration).await;
+}
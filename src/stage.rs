@@ -1,7 +1,19 @@
-use crate::{envelope::*, proxy::*, remote::*, Actor};
-use futures::{channel::mpsc, prelude::*};
+use crate::{
+    blocking, context::*, envelope::*, id, mailbox::*, panic_message, proxy::*, registry,
+    remote::*, Actor, ActorId, DeadLetter, ErasedMessage, RegistryError, SharedAccess,
+    StreamHandler,
+};
+use futures::{
+    future::BoxFuture,
+    prelude::*,
+    stream::{FuturesUnordered, SelectAll},
+    task::{self, Poll},
+};
+use log::*;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use std::{marker::PhantomData, sync::Arc};
+use std::{
+    any::Any, collections::VecDeque, marker::PhantomData, panic::AssertUnwindSafe, sync::Arc,
+};
 
 /// Builder for initializing an actor that needs its own [`Remote`].
 ///
@@ -34,21 +46,24 @@ use std::{marker::PhantomData, sync::Arc};
 /// [`Remote`]: struct.Remote.html
 pub struct StageBuilder<A: Actor> {
     remote: Arc<RemoteInner>,
-    receiver: mpsc::Receiver<Envelope<A>>,
+    receiver: MailboxReceiver<A>,
     proxy: ProxyFor<A>,
+    _guard: ExitGuard,
     _marker: PhantomData<A>,
 }
 
 impl<A: Actor> StageBuilder<A> {
     pub fn new() -> (Self, Remote<A>) {
         let remote_inner = Arc::new(RemoteInner::new(ActorState::Building));
+        id::register(&remote_inner);
 
-        let (sender, receiver) = mpsc::channel(16);
-        let proxy = ProxyFor::new(sender);
+        let (sender, receiver) = mailbox(remote_inner.id());
+        let proxy = ProxyFor::new(sender, remote_inner.id());
 
         let remote = Remote::new(remote_inner.clone(), &proxy);
 
         let builder = Self {
+            _guard: ExitGuard::new(remote_inner.clone()),
             remote: remote_inner,
             receiver,
             proxy,
@@ -58,12 +73,91 @@ impl<A: Actor> StageBuilder<A> {
         (builder, remote)
     }
 
+    /// Returns the ID that will be assigned to the actor.
+    ///
+    /// The ID is allocated when the builder is created, so it can be handed out before
+    /// the actor itself has been created.
+    pub fn id(&self) -> ActorId {
+        self.remote.id()
+    }
+
+    /// Sets the maximum number of messages that can be queued in the actor's mailbox.
+    ///
+    /// Defaults to the actor's [`MAILBOX_CAPACITY`]. What happens when a message is sent
+    /// to an actor with a full mailbox is determined by the [overflow policy].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    ///
+    /// [`MAILBOX_CAPACITY`]: trait.Actor.html#associatedconstant.MAILBOX_CAPACITY
+    /// [overflow policy]: #method.overflow_policy
+    pub fn capacity(self, capacity: usize) -> Self {
+        assert!(capacity > 0, "Mailbox capacity must be greater than 0");
+        self.receiver.set_capacity(Some(capacity));
+        self
+    }
+
+    /// Removes the limit on the number of messages that can be queued in the actor's
+    /// mailbox.
+    pub fn unbounded(self) -> Self {
+        self.receiver.set_capacity(None);
+        self
+    }
+
+    /// Sets what happens when a message is sent to the actor while its mailbox is full.
+    ///
+    /// Defaults to [`OverflowPolicy::RejectNewest`].
+    ///
+    /// [`OverflowPolicy::RejectNewest`]: enum.OverflowPolicy.html#variant.RejectNewest
+    pub fn overflow_policy(self, policy: OverflowPolicy) -> Self {
+        self.receiver.set_overflow(policy);
+        self
+    }
+
+    /// Sets the handler for messages that couldn't be delivered to the actor, overriding
+    /// the global handler set with [`set_dead_letter_handler`].
+    ///
+    /// See [`DeadLetter`] for more details.
+    ///
+    /// [`set_dead_letter_handler`]: fn.set_dead_letter_handler.html
+    /// [`DeadLetter`]: struct.DeadLetter.html
+    pub fn dead_letters<F>(self, handler: F) -> Self
+    where
+        F: Fn(DeadLetter) + Send + Sync + 'static,
+    {
+        self.receiver.set_dead_letters(Arc::new(handler));
+        self
+    }
+
+    /// Registers the actor under `name`, allowing other code to get a proxy to the
+    /// actor using [`lookup`].
+    ///
+    /// The actor is registered immediately, so messages sent to it before the stage is
+    /// running will be queued as normal. The actor is removed from the registry once it
+    /// stops. Fails if another running actor is already registered under `name`.
+    ///
+    /// [`lookup`]: fn.lookup.html
+    pub fn name(self, name: impl Into<String>) -> Result<Self, RegistryError> {
+        let name = name.into();
+        registry::register(&name, &self.proxy, &self.remote)?;
+        self.remote.set_name(name);
+        Ok(self)
+    }
+
     pub fn finish(self, actor: A) -> Stage<A> {
         Stage {
             actor,
-            receiver: self.receiver,
-            proxy: self.proxy,
+            inbox: Inbox {
+                receiver: self.receiver,
+                tasks: SelectAll::new(),
+                ready: VecDeque::new(),
+                deferred: None,
+                mailbox_turn: false,
+            },
+            context: Context::new(self.remote.clone(), self.proxy),
             remote: self.remote,
+            _guard: self._guard,
         }
     }
 
@@ -74,13 +168,26 @@ impl<A: Actor> StageBuilder<A> {
         crate::runtime::spawn(stage.run());
         proxy
     }
+
+    /// Spawns the actor, returning a proxy and a [`JoinHandle`] that resolves once the
+    /// actor has finished running.
+    ///
+    /// [`JoinHandle`]: struct.JoinHandle.html
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn spawn_with_handle(self, actor: A) -> (A::Proxy, JoinHandle) {
+        let stage = self.finish(actor);
+        let proxy = stage.proxy();
+        let handle = stage.join_handle();
+        crate::runtime::spawn(stage.run());
+        (proxy, handle)
+    }
 }
 
 pub struct Stage<A: Actor> {
-    actor: A,
-    receiver: mpsc::Receiver<Envelope<A>>,
+    pub(crate) actor: A,
+    inbox: Inbox<A>,
 
-    // Hold onto a proxy for the actor.
+    // The context holds onto a proxy for the actor.
     //
     // NOTE: We can't hold a `WeakProxyFor<A>` here because that would mean that, once
     // all externally-held proxies had been dropped, there would be no way to construct
@@ -88,27 +195,104 @@ pub struct Stage<A: Actor> {
     // the remote only holds a weak reference to the proxy count, this also ensures that
     // a new proxy can't be created once the actor has been stopped and the stage has
     // been dropped.
-    proxy: ProxyFor<A>,
+    context: Context<A>,
 
     /// Share a reference to the `RemoteInner` so that we can check the state.
-    remote: Arc<RemoteInner>,
+    pub(crate) remote: Arc<RemoteInner>,
+
+    // NOTE: This must be the last field, so that the mailbox has been closed and its
+    // remaining messages discarded by the time the guard finishes the actor.
+    _guard: ExitGuard,
 }
 
 impl<A: Actor> Stage<A> {
     /// Consumes the stage, returning a future tha will run the actor until it is stopped.
-    pub async fn run(mut self) {
-        // Mark that the actor is running.
-        //
-        // TODO: Do we have to do anything here to handle the case where the actor has
-        // already been asked to stop? Is that a valid case, or would we reject any stop
-        // requests that come in before the actor has started running?
-        self.remote.set_state(ActorState::Running);
-
-        // TODO: What would it mean for `stream.next()` to return `None` here? Since the stage
-        // holds onto a copy of the proxy, that case should never happen right?
-        while let Some(envelope) = self.receiver.next().await {
+    ///
+    /// If the actor panics, either while handling a message or in one of its lifecycle
+    /// hooks, the panic is caught and the actor transitions to [`ActorState::Failed`].
+    /// Any messages remaining in the actor's mailbox are discarded, and any pending
+    /// requests will resolve to an error.
+    ///
+    /// [`ActorState::Failed`]: enum.ActorState.html#variant.Failed
+    pub async fn run(self) {
+        self.run_to_completion().await;
+    }
+
+    /// Consumes the stage, returning a future that will run the actor until it is
+    /// stopped and then yield the final actor value.
+    ///
+    /// This behaves the same as [`run`], except that the actor isn't dropped once it
+    /// has finished running. This is useful for inspecting the actor's state after it
+    /// has stopped, e.g. in tests, or for handing its state over to a new actor.
+    ///
+    /// Returns `None` if the actor panicked, since the actor may have been left in an
+    /// inconsistent state.
+    ///
+    /// [`run`]: #method.run
+    pub async fn run_to_completion(mut self) -> Option<A> {
+        // Mark that the actor is running. If the actor was already asked to stop (e.g.
+        // by an `ActorSystem` shutting down) it still runs its lifecycle hooks, but stops
+        // before handling any messages.
+        self.remote.start();
+
+        match self.run_catching().await {
+            // Mark that the actor has fully stopped.
+            Ok(reason) => {
+                self.remote.finish(reason);
+                Some(self.actor)
+            }
+
+            Err(payload) => {
+                let message = panic_message(&*payload);
+                error!("Actor {} panicked, stopping actor: {}", self.id(), message);
+
+                // The actor may have been left in an inconsistent state by the panic, so it's
+                // not safe to continue handling messages.
+                self.shutdown(ExitReason::Panicked(message));
+                None
+            }
+        }
+    }
+
+    /// Runs the actor, catching any panic that occurs while it is running.
+    ///
+    /// The mailbox is left untouched if the actor panics, so that a supervisor can
+    /// replace the actor and continue processing messages.
+    pub(crate) async fn run_catching(&mut self) -> Result<ExitReason, Box<dyn Any + Send>> {
+        blocking::in_actor(AssertUnwindSafe(self.run_actor()).catch_unwind()).await
+    }
+
+    /// Stops the stage without handling any further messages.
+    ///
+    /// Closes the channel so that no new messages can be sent, then discards any
+    /// remaining messages as dead letters. Dropping the messages ensures that any
+    /// pending requests are notified that the actor stopped.
+    pub(crate) fn shutdown(&mut self, reason: ExitReason) {
+        self.inbox.receiver.close();
+        self.clear_tasks();
+        self.remote.set_state(reason.state());
+        if let Some(message) = self.inbox.deferred.take() {
+            self.inbox.receiver.discard(message);
+        }
+        self.inbox.receiver.discard_remaining();
+        self.remote.finish(reason);
+    }
+
+    /// Runs the actor through its full lifecycle, processing messages until it stops.
+    async fn run_actor(&mut self) -> ExitReason {
+        self.actor.started().await;
+
+        let reason = loop {
+            // NOTE: The mailbox only yields `None` once it has been closed, which only happens
+            // after this loop exits, so `None` here means the actor was stopped while waiting
+            // for a message.
+            let envelope = match self.next_envelope().await {
+                Some(envelope) => envelope,
+                None => break ExitReason::Stopped,
+            };
+
             match envelope {
-                Envelope::Message(message) => message.handle(&mut self.actor).await,
+                Envelope::Message(message) => self.dispatch(message).await,
 
                 // NOTE: We don't need to do anything in the case that a proxy was dropped, since
                 // we check the proxy count at the end of the loop body.
@@ -116,44 +300,266 @@ impl<A: Actor> Stage<A> {
             }
 
             // Check if the actor has stopped itself after each message we process.
-            //
-            // TODO: Do we need to be able to stop the actor while still waiting for a message?
-            // It's technically valid currently for the actor to hand off its remote to another
-            // task/thread that could stop the actor at an arbitrary time, though doing so is
-            // not the intended use case.
             if self.remote.state() == ActorState::Stopping {
-                break;
+                break ExitReason::Stopped;
             }
 
             // Check if there are any proxies held by other tasks. As long as the actor is running
             // there will be at least one proxy, since the stage holds onto one itself. If the
             // count drops to one, that means no other tasks are holding onto proxies and we
             // therefore cannot receive any new messages.
-            if self.proxy.count() == 1 {
-                break;
+            if self.context.proxy.count() == 1 {
+                break ExitReason::ProxiesDropped;
             }
-        }
+        };
 
-        // Close the channel so that no new messages can be sent.
-        self.receiver.close();
+        // Close the channel so that no new messages can be sent, and drop any futures
+        // spawned by the actor.
+        self.inbox.receiver.close();
+        self.clear_tasks();
+        self.actor.stopping().await;
 
-        // Process any remaining messages.
-        while let Some(envelope) = self.receiver.next().await {
+        // Process any remaining messages, starting with any message that was put aside
+        // while handling read-only messages.
+        if let Some(message) = self.inbox.deferred.take() {
+            self.handle(message).await;
+        }
+        while let Some(envelope) = self.inbox.receiver.next().await {
             match envelope {
-                Envelope::Message(message) => message.handle(&mut self.actor).await,
+                Envelope::Message(message) => self.handle(message).await,
                 Envelope::ProxyDropped => {}
             }
         }
 
-        // Mark that the actor has fully stopped.
-        self.remote.set_state(ActorState::Stopped);
+        self.clear_tasks();
+        self.actor.stopped().await;
+
+        reason
+    }
+
+    /// Handles a message, running it concurrently with any following read-only messages
+    /// if the actor supports it.
+    ///
+    /// See [`Actor::shared_access`] for more details.
+    ///
+    /// [`Actor::shared_access`]: trait.Actor.html#method.shared_access
+    async fn dispatch(&mut self, message: Box<dyn ErasedMessage<A>>) {
+        let Stage {
+            actor,
+            inbox,
+            remote,
+            ..
+        } = self;
+
+        let message = match actor.shared_access() {
+            Some(access) => match access.handle(message) {
+                Ok(reading) => return read_concurrently(access, inbox, remote, reading).await,
+                Err(message) => message,
+            },
+            None => message,
+        };
+
+        self.handle(message).await;
+    }
+
+    /// Handles a single message, continuing to run the actor's spawned futures while
+    /// the handler runs.
+    ///
+    /// Futures spawned by the handler start running once the handler has completed.
+    async fn handle(&mut self, message: Box<dyn ErasedMessage<A>>) {
+        let Stage {
+            actor,
+            context,
+            inbox,
+            ..
+        } = self;
+
+        let mut handling = message.handle(actor, context);
+        future::poll_fn(|cx| {
+            if handling.poll_unpin(cx).is_ready() {
+                return Poll::Ready(());
+            }
+
+            inbox.poll_tasks(cx);
+            Poll::Pending
+        })
+        .await;
+        drop(handling);
+
+        self.inbox.tasks.extend(self.context.take_tasks());
+    }
+
+    /// Drops any futures spawned by the actor, along with any messages they produced that
+    /// haven't been handled yet.
+    pub(crate) fn clear_tasks(&mut self) {
+        self.context.take_tasks();
+        self.inbox.tasks.clear();
+        self.inbox.ready.clear();
+    }
+
+    /// Waits for the next message to handle.
+    ///
+    /// Returns `None` if the actor is stopped via its [`Remote`] while waiting, so that
+    /// an idle actor can be stopped without having to send it a message first.
+    fn next_envelope(&mut self) -> impl Future<Output = Option<Envelope<A>>> + '_ {
+        let Stage { inbox, remote, .. } = self;
+        future::poll_fn(move |cx| inbox.poll_next(remote, cx))
     }
 
     pub fn proxy(&self) -> A::Proxy {
-        A::Proxy::new(self.proxy.clone())
+        self.context.proxy()
+    }
+
+    /// Returns the ID of the actor.
+    pub fn id(&self) -> ActorId {
+        self.remote.id()
+    }
+
+    /// Attaches a stream to the actor before it starts running, so that each item in
+    /// the stream is handled by the actor as a message.
+    ///
+    /// See [`StreamHandler`] for more details. Once the actor is running, it can attach
+    /// further streams to itself using its [`Context`].
+    ///
+    /// [`StreamHandler`]: trait.StreamHandler.html
+    /// [`Context`]: struct.Context.html#method.add_stream
+    pub fn add_stream<S>(&mut self, stream: S)
+    where
+        S: Stream + Send + 'static,
+        S::Item: 'static + Send,
+        A: StreamHandler<S::Item>,
+    {
+        self.context.add_stream(stream);
+        self.inbox.tasks.extend(self.context.take_tasks());
+    }
+
+    /// Returns a future that resolves once the actor has finished running.
+    pub fn join_handle(&self) -> JoinHandle {
+        JoinHandle::new(self.remote.clone())
+    }
+}
+
+/// The sources of messages for an actor.
+struct Inbox<A: Actor> {
+    receiver: MailboxReceiver<A>,
+
+    /// Futures, timers and streams spawned by the actor through its context.
+    tasks: SelectAll<Task<A>>,
+
+    /// Messages produced by tasks while the actor was busy, waiting to be handled.
+    ready: VecDeque<Box<dyn ErasedMessage<A>>>,
+
+    /// A message that ended a run of read-only messages, which must be handled next.
+    deferred: Option<Box<dyn ErasedMessage<A>>>,
+
+    /// Whether the mailbox should be checked for messages before the actor's tasks.
+    ///
+    /// Alternates between the two sources so that neither one can starve the other.
+    mailbox_turn: bool,
+}
+
+impl<A: Actor> Inbox<A> {
+    /// Polls for the next message to handle.
+    ///
+    /// Messages produced by the actor's tasks, i.e. its timers and attached streams, are
+    /// interleaved with messages from the mailbox: Whenever both have a message ready,
+    /// they take turns.
+    fn poll_next(
+        &mut self,
+        remote: &RemoteInner,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<Envelope<A>>> {
+        remote.register_waker(cx.waker());
+        if remote.state() == ActorState::Stopping {
+            return Poll::Ready(None);
+        }
+
+        if let Some(message) = self.deferred.take() {
+            return Poll::Ready(Some(Envelope::Message(message)));
+        }
+
+        if self.mailbox_turn {
+            if let Poll::Ready(envelope) = self.receiver.poll_next_unpin(cx) {
+                self.mailbox_turn = false;
+                return Poll::Ready(envelope);
+            }
+        }
+
+        let message = match self.ready.pop_front() {
+            Some(message) => Some(message),
+            None => match self.tasks.poll_next_unpin(cx) {
+                Poll::Ready(message) => message,
+                Poll::Pending => None,
+            },
+        };
+        if let Some(message) = message {
+            self.mailbox_turn = true;
+            return Poll::Ready(Some(Envelope::Message(message)));
+        }
+
+        let envelope = futures::ready!(self.receiver.poll_next_unpin(cx));
+        self.mailbox_turn = false;
+        Poll::Ready(envelope)
+    }
+
+    /// Polls the actor's tasks while the actor is busy, queuing any messages they produce.
+    fn poll_tasks(&mut self, cx: &mut task::Context<'_>) {
+        while let Poll::Ready(Some(message)) = self.tasks.poll_next_unpin(cx) {
+            self.ready.push_back(message);
+        }
     }
 }
 
+/// Runs a read-only message, along with any read-only messages that arrive while it's
+/// running, until all of them have completed.
+///
+/// The first message that needs exclusive access to the actor ends the run, and is put
+/// aside to be handled once all of the running messages have completed. This gives
+/// readers-writer semantics: Messages that only read from the actor can run
+/// concurrently, but never at the same time as a message that modifies the actor.
+async fn read_concurrently<'a, A: Actor>(
+    access: SharedAccess<'a, A>,
+    inbox: &mut Inbox<A>,
+    remote: &RemoteInner,
+    reading: BoxFuture<'a, ()>,
+) {
+    let mut running = FuturesUnordered::new();
+    running.push(reading);
+
+    let mut accepting = true;
+    future::poll_fn(|cx| {
+        while accepting {
+            match inbox.poll_next(remote, cx) {
+                Poll::Ready(Some(Envelope::Message(message))) => match access.handle(message) {
+                    Ok(reading) => running.push(reading),
+                    Err(message) => {
+                        inbox.deferred = Some(message);
+                        accepting = false;
+                    }
+                },
+
+                // NOTE: The stage checks the proxy count once all of the running messages
+                // have completed, so there's nothing to do if a proxy was dropped.
+                Poll::Ready(Some(Envelope::ProxyDropped)) => {}
+
+                // The actor is stopping, so stop accepting messages. The stage notices
+                // the state change once the running messages have completed.
+                Poll::Ready(None) => accepting = false,
+
+                Poll::Pending => break,
+            }
+        }
+
+        while let Poll::Ready(Some(())) = running.poll_next_unpin(cx) {}
+        if running.is_empty() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 pub enum ActorState {
@@ -162,4 +568,39 @@ pub enum ActorState {
     Running,
     Stopping,
     Stopped,
+
+    /// The actor panicked and was stopped without handling any further messages.
+    Failed,
+}
+
+/// The reason an actor stopped running.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ExitReason {
+    /// The actor was stopped using its [`Remote`].
+    ///
+    /// [`Remote`]: struct.Remote.html
+    Stopped,
+
+    /// All proxies to the actor were dropped, so it could no longer receive messages.
+    ProxiesDropped,
+
+    /// The actor panicked. Contains the message from the panic payload.
+    Panicked(String),
+
+    /// The actor's [`Stage`] was dropped before the actor finished running, e.g.
+    /// because the stage was never run, or the future running it was cancelled.
+    ///
+    /// [`Stage`]: struct.Stage.html
+    Dropped,
+}
+
+impl ExitReason {
+    /// The state the actor is left in after exiting for this reason.
+    pub(crate) fn state(&self) -> ActorState {
+        match self {
+            ExitReason::Panicked(_) => ActorState::Failed,
+            _ => ActorState::Stopped,
+        }
+    }
 }
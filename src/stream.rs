@@ -0,0 +1,118 @@
+//! Support for attaching streams to an actor, so that each item in the stream is
+//! delivered to the actor as a message.
+
+use crate::{context::Task, message::Handled, proxy::ProxyFor, Context, ErasedMessage, Handler};
+use futures::{future::BoxFuture, prelude::*, stream};
+use std::marker::PhantomData;
+
+/// Implemented by actors that can have a stream of items of type `I` attached to them.
+///
+/// Each item in the stream is handled using the actor's [`Handler<I>`] impl, and
+/// `finished` is called once the stream has ended. Attach a stream to an actor using
+/// [`Stage::add_stream`] or [`Context::add_stream`].
+///
+/// Items from attached streams are interleaved fairly with messages from the actor's
+/// mailbox, so a busy stream can't prevent the actor from handling messages sent to it
+/// through its proxies. While a stream is attached the actor won't stop due to all of
+/// its proxies being dropped, though it can still be stopped explicitly.
+///
+/// # Examples
+///
+/// ```
+/// use futures::{future::BoxFuture, prelude::*, stream};
+/// use thespian::{Actor, Context, Handler, StreamHandler};
+///
+/// pub struct Line(String);
+///
+/// #[derive(Default, Actor)]
+/// pub struct Reader {
+///     lines: Vec<String>,
+/// }
+///
+/// #[thespian::actor]
+/// impl Reader {}
+///
+/// impl Handler<Line> for Reader {
+///     type Output = ();
+///
+///     fn handle(&mut self, line: Line, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+///         self.lines.push(line.0);
+///         future::ready(()).boxed()
+///     }
+/// }
+///
+/// impl StreamHandler<Line> for Reader {
+///     fn finished(&mut self, ctx: &mut Context<Self>) -> BoxFuture<'_, ()> {
+///         ctx.stop();
+///         future::ready(()).boxed()
+///     }
+/// }
+///
+/// let mut stage = Reader::default().into_stage();
+/// stage.add_stream(stream::iter(vec![Line("hello".into()), Line("world".into())]));
+/// ```
+///
+/// [`Handler<I>`]: trait.Handler.html
+/// [`Stage::add_stream`]: struct.Stage.html#method.add_stream
+/// [`Context::add_stream`]: struct.Context.html#method.add_stream
+pub trait StreamHandler<I: 'static + Send>: Handler<I> {
+    /// Called once an attached stream of `I` has ended, after all of its items have been
+    /// handled.
+    ///
+    /// Not called if the actor stops before the stream ends.
+    fn finished(&mut self, _ctx: &mut Context<Self>) -> BoxFuture<'_, ()> {
+        future::ready(()).boxed()
+    }
+}
+
+/// Converts a stream into a task that yields a message for each item, followed by a
+/// message notifying the actor that the stream has ended.
+pub(crate) fn attach<A, S>(stream: S, proxy: ProxyFor<A>) -> Task<A>
+where
+    A: StreamHandler<S::Item>,
+    S: Stream + Send + 'static,
+    S::Item: 'static + Send,
+{
+    let finished: Box<dyn ErasedMessage<A>> = Box::new(Finished::<S::Item, A> {
+        proxy,
+        _item: PhantomData,
+    });
+
+    stream
+        .map(|item| Box::new(Handled::<A, S::Item>::new(item)) as Box<dyn ErasedMessage<A>>)
+        .chain(stream::once(future::ready(finished)))
+        .boxed()
+}
+
+/// The message delivered to an actor once an attached stream has ended.
+struct Finished<I, A: StreamHandler<I>>
+where
+    I: 'static + Send,
+{
+    // NOTE: Holding onto a proxy keeps the actor from stopping due to its proxies being
+    // dropped while the stream is attached. The proxy is only dropped once the actor has
+    // been notified that the stream ended, so that the notification is always handled.
+    proxy: ProxyFor<A>,
+    _item: PhantomData<fn() -> I>,
+}
+
+impl<I, A> ErasedMessage<A> for Finished<I, A>
+where
+    I: 'static + Send,
+    A: StreamHandler<I>,
+{
+    fn handle<'a>(self: Box<Self>, actor: &'a mut A, ctx: &'a mut Context<A>) -> BoxFuture<'a, ()> {
+        drop(self.proxy);
+        actor.finished(ctx)
+    }
+
+    fn handle_shared<'a>(
+        self: Box<Self>,
+        _actor: &'a A,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<A>>>
+    where
+        for<'x> &'x A: Send,
+    {
+        Err(self)
+    }
+}
@@ -0,0 +1,350 @@
+//! Supervision of actors that can be restarted when they fail.
+//!
+//! A [`Supervisor`] owns a factory function for each of its child actors. When a
+//! child panics, the supervisor uses the factory to create a new actor value, which
+//! then continues handling messages from the same mailbox. This means that any
+//! proxies to the child remain valid across restarts.
+//!
+//! [`Supervisor`]: struct.Supervisor.html
+
+use crate::{panic_message, stage::*, Actor, ActorId};
+use futures::{
+    channel::oneshot,
+    future::{self, BoxFuture, Either},
+    prelude::*,
+    stream::FuturesUnordered,
+};
+use log::*;
+use std::{
+    collections::{BTreeSet, VecDeque},
+    mem,
+    time::{Duration, Instant},
+};
+
+/// Determines which children are restarted when one of a supervisor's children fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RestartStrategy {
+    /// Only the failed child is restarted.
+    OneForOne,
+
+    /// All children are restarted when any one of them fails.
+    OneForAll,
+
+    /// The failed child and all children added to the supervisor after it are
+    /// restarted.
+    RestForOne,
+}
+
+/// Runs a group of child actors, restarting them when they fail.
+///
+/// Children are restarted only if they panic. A child that stops normally, either
+/// because it stopped itself or because all proxies to it were dropped, is not
+/// restarted. Once all children have stopped the supervisor stops as well.
+///
+/// If the children fail more than the configured number of times within the restart
+/// window, the supervisor gives up: All of its children are stopped without handling
+/// any further messages and the supervisor stops.
+///
+/// Children are run concurrently within the supervisor's task, so the supervisor must
+/// be run in order for its children to make progress, either by spawning it with
+/// [`spawn`] or by awaiting the future returned by [`run`].
+///
+/// # Examples
+///
+/// ```
+/// use thespian::{Actor, RestartStrategy, Supervisor};
+/// use std::time::Duration;
+///
+/// #[derive(Default, Actor)]
+/// pub struct MyActor {
+///     count: usize,
+/// }
+///
+/// #[thespian::actor]
+/// impl MyActor {}
+///
+/// let mut supervisor = Supervisor::new(RestartStrategy::OneForOne)
+///     .max_restarts(5, Duration::from_secs(10));
+/// let proxy = supervisor.add_child(MyActor::default);
+/// let future = supervisor.run();
+/// ```
+///
+/// [`spawn`]: #method.spawn
+/// [`run`]: #method.run
+pub struct Supervisor {
+    strategy: RestartStrategy,
+    max_restarts: usize,
+    within: Duration,
+    children: Vec<Box<dyn SupervisedChild>>,
+}
+
+impl Supervisor {
+    /// Creates a new supervisor using the specified restart strategy.
+    ///
+    /// By default, the supervisor allows at most 3 restarts within 5 seconds.
+    pub fn new(strategy: RestartStrategy) -> Self {
+        Self {
+            strategy,
+            max_restarts: 3,
+            within: Duration::from_secs(5),
+            children: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of restarts allowed within the given time window.
+    pub fn max_restarts(mut self, max_restarts: usize, within: Duration) -> Self {
+        self.max_restarts = max_restarts;
+        self.within = within;
+        self
+    }
+
+    /// Adds a child actor, returning a proxy to it.
+    ///
+    /// The factory is invoked once immediately to create the initial actor, and again
+    /// each time the actor is restarted.
+    pub fn add_child<A, F>(&mut self, factory: F) -> A::Proxy
+    where
+        A: Actor,
+        F: FnMut() -> A + Send + 'static,
+    {
+        let (builder, _) = StageBuilder::new();
+        self.add_child_with(builder, factory)
+    }
+
+    /// Adds a child actor using an existing [`StageBuilder`], returning a proxy to it.
+    ///
+    /// This allows the factory to capture the child's [`Remote`] so that each new
+    /// instance of the actor can be given a copy.
+    ///
+    /// [`StageBuilder`]: struct.StageBuilder.html
+    /// [`Remote`]: struct.Remote.html
+    pub fn add_child_with<A, F>(&mut self, builder: StageBuilder<A>, mut factory: F) -> A::Proxy
+    where
+        A: Actor,
+        F: FnMut() -> A + Send + 'static,
+    {
+        let stage = builder.finish(factory());
+        let proxy = stage.proxy();
+        self.children.push(Box::new(Child { stage, factory }));
+        proxy
+    }
+
+    /// Consumes the supervisor, returning a future that will run its children until
+    /// they have all stopped.
+    pub async fn run(self) {
+        let Supervisor {
+            strategy,
+            max_restarts,
+            within,
+            children,
+        } = self;
+
+        let child_count = children.len();
+        let mut running = FuturesUnordered::new();
+        let mut cancels = (0..child_count).map(|_| None).collect::<Vec<_>>();
+        let mut parked = (0..child_count).map(|_| None).collect::<Vec<_>>();
+        let mut restart_times = VecDeque::new();
+
+        // Children that need to be restarted once they have all finished running.
+        let mut pending = BTreeSet::new();
+
+        for (index, child) in children.into_iter().enumerate() {
+            cancels[index] = Some(start_child(&mut running, index, child));
+        }
+
+        while let Some((index, child, exit)) = running.next().await {
+            cancels[index] = None;
+
+            match exit {
+                ChildExit::Stopped => {
+                    pending.remove(&index);
+                }
+
+                ChildExit::Cancelled => parked[index] = Some(child),
+
+                // If the child was already going to be restarted, we don't need to do
+                // anything else.
+                ChildExit::Failed(_) if pending.contains(&index) => parked[index] = Some(child),
+
+                ChildExit::Failed(message) => {
+                    error!("Supervised actor {} panicked: {}", child.id(), message);
+                    parked[index] = Some(child);
+
+                    // Discard any restarts that have fallen outside of the window, then
+                    // check if this restart would exceed the maximum number of restarts.
+                    let now = Instant::now();
+                    while restart_times
+                        .front()
+                        .map(|&time| now.duration_since(time) > within)
+                        .unwrap_or(false)
+                    {
+                        restart_times.pop_front();
+                    }
+
+                    if restart_times.len() >= max_restarts {
+                        error!("Supervised actors restarted too many times, stopping supervisor");
+                        give_up(index, message, running, cancels, parked).await;
+                        return;
+                    }
+
+                    restart_times.push_back(now);
+
+                    // Determine which children need to be restarted and cancel any of them
+                    // that are still running.
+                    pending.insert(index);
+                    let siblings = match strategy {
+                        RestartStrategy::OneForOne => 0..0,
+                        RestartStrategy::OneForAll => 0..child_count,
+                        RestartStrategy::RestForOne => index..child_count,
+                    };
+                    for sibling in siblings {
+                        if let Some(cancel) = cancels[sibling].take() {
+                            let _ = cancel.send(());
+                            pending.insert(sibling);
+                        }
+                    }
+                }
+            }
+
+            // Once all of the children that need to be restarted have finished running,
+            // restart them in the order they were added to the supervisor.
+            if pending.iter().all(|&index| parked[index].is_some()) {
+                for index in mem::take(&mut pending) {
+                    let mut child: Box<dyn SupervisedChild> = parked[index].take().unwrap();
+                    child.restart();
+                    cancels[index] = Some(start_child(&mut running, index, child));
+                }
+            }
+        }
+    }
+
+    /// Spawns the supervisor onto the runtime, running its children until they have all
+    /// stopped.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn spawn(self) {
+        crate::runtime::spawn(self.run());
+    }
+}
+
+type ChildFuture = BoxFuture<'static, (usize, Box<dyn SupervisedChild>, ChildExit)>;
+
+fn start_child(
+    running: &mut FuturesUnordered<ChildFuture>,
+    index: usize,
+    child: Box<dyn SupervisedChild>,
+) -> oneshot::Sender<()> {
+    let (cancel, cancelled) = oneshot::channel();
+    running.push(
+        child
+            .run(cancelled)
+            .map(move |(child, exit)| (index, child, exit))
+            .boxed(),
+    );
+    cancel
+}
+
+/// Stops all children after the supervisor has exceeded its restart intensity.
+async fn give_up(
+    failed: usize,
+    message: String,
+    mut running: FuturesUnordered<ChildFuture>,
+    cancels: Vec<Option<oneshot::Sender<()>>>,
+    mut parked: Vec<Option<Box<dyn SupervisedChild>>>,
+) {
+    for cancel in cancels.into_iter().flatten() {
+        let _ = cancel.send(());
+    }
+
+    while let Some((index, child, _)) = running.next().await {
+        parked[index] = Some(child);
+    }
+
+    for (index, child) in parked.iter_mut().enumerate() {
+        if let Some(child) = child {
+            let reason = if index == failed {
+                ExitReason::Panicked(message.clone())
+            } else {
+                ExitReason::Stopped
+            };
+            child.shutdown(reason);
+        }
+    }
+}
+
+/// The reason a supervised child stopped running.
+enum ChildExit {
+    /// The child stopped normally and will not be restarted.
+    Stopped,
+
+    /// The child panicked. Contains the panic message.
+    Failed(String),
+
+    /// The child was cancelled by the supervisor in order to be restarted.
+    Cancelled,
+}
+
+/// Type-erased interface for the supervisor to manage its children.
+trait SupervisedChild: Send {
+    /// Runs the child until it stops, panics, or is cancelled.
+    fn run(
+        self: Box<Self>,
+        cancelled: oneshot::Receiver<()>,
+    ) -> BoxFuture<'static, (Box<dyn SupervisedChild>, ChildExit)>;
+
+    /// Replaces the child's actor with a new one created by its factory.
+    fn restart(&mut self);
+
+    /// Returns the ID of the child, which stays the same across restarts.
+    fn id(&self) -> ActorId;
+
+    /// Stops the child without handling any further messages.
+    fn shutdown(&mut self, reason: ExitReason);
+}
+
+struct Child<A: Actor, F> {
+    stage: Stage<A>,
+    factory: F,
+}
+
+impl<A, F> SupervisedChild for Child<A, F>
+where
+    A: Actor,
+    F: FnMut() -> A + Send + 'static,
+{
+    fn run(
+        mut self: Box<Self>,
+        cancelled: oneshot::Receiver<()>,
+    ) -> BoxFuture<'static, (Box<dyn SupervisedChild>, ChildExit)> {
+        async move {
+            let remote = self.stage.remote.clone();
+            remote.start();
+
+            let exit = match future::select(self.stage.run_catching().boxed(), cancelled).await {
+                Either::Left((Ok(reason), _)) => {
+                    remote.finish(reason);
+                    ChildExit::Stopped
+                }
+
+                Either::Left((Err(payload), _)) => ChildExit::Failed(panic_message(&*payload)),
+
+                Either::Right(_) => ChildExit::Cancelled,
+            };
+
+            (self as Box<dyn SupervisedChild>, exit)
+        }
+        .boxed()
+    }
+
+    fn restart(&mut self) {
+        self.stage.actor = (self.factory)();
+        self.stage.clear_tasks();
+    }
+
+    fn id(&self) -> ActorId {
+        self.stage.id()
+    }
+
+    fn shutdown(&mut self, reason: ExitReason) {
+        self.stage.shutdown(reason);
+    }
+}
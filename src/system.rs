@@ -0,0 +1,165 @@
+//! Tracking a group of actors so that they can be shut down together.
+
+use crate::{
+    remote::{JoinHandle, RemoteInner},
+    stage::*,
+    Actor,
+};
+use std::{
+    any,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Owns a group of actors so that they can be inspected and shut down together.
+///
+/// Every actor spawned through an `ActorSystem` is tracked until it stops. Calling
+/// [`shutdown`] stops all of the system's actors, in the reverse of the order they
+/// were spawned in. This means that actors spawned later, which may depend on actors
+/// spawned earlier, are stopped before their dependencies.
+///
+/// `ActorSystem` is cheap to clone, and all clones refer to the same group of actors.
+///
+/// # Examples
+///
+/// ```
+/// use futures::{executor::block_on, future};
+/// use thespian::{Actor, ActorSystem};
+///
+/// #[derive(Actor)]
+/// pub struct MyActor;
+///
+/// #[thespian::actor]
+/// impl MyActor {}
+///
+/// let system = ActorSystem::new();
+/// let stage = MyActor.into_stage();
+/// system.track(&stage);
+/// assert_eq!(1, system.len());
+///
+/// block_on(future::join(stage.run(), system.shutdown()));
+/// assert!(system.is_empty());
+/// ```
+///
+/// [`shutdown`]: #method.shutdown
+#[derive(Debug, Clone, Default)]
+pub struct ActorSystem {
+    actors: Arc<Mutex<Vec<ActorInfo>>>,
+}
+
+impl ActorSystem {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Spawns the actor onto the runtime, tracking it as part of the system.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn spawn<A: Actor>(&self, actor: A) -> A::Proxy {
+        let (builder, _) = StageBuilder::new();
+        self.spawn_with(builder, actor)
+    }
+
+    /// Spawns the actor using an existing [`StageBuilder`], tracking it as part of the
+    /// system.
+    ///
+    /// [`StageBuilder`]: struct.StageBuilder.html
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn spawn_with<A: Actor>(&self, builder: StageBuilder<A>, actor: A) -> A::Proxy {
+        let stage = builder.finish(actor);
+        let proxy = stage.proxy();
+        self.track(&stage);
+        crate::runtime::spawn(stage.run());
+        proxy
+    }
+
+    /// Tracks the stage as part of the system.
+    ///
+    /// This is useful when not using one of the supported runtimes, since the system
+    /// doesn't need to be the one to spawn the stage in order to stop it. The stage must
+    /// still be run in order for [`shutdown`] to complete.
+    ///
+    /// [`shutdown`]: #method.shutdown
+    pub fn track<A: Actor>(&self, stage: &Stage<A>) {
+        let mut actors = self.lock();
+        actors.retain(ActorInfo::is_live);
+        actors.push(ActorInfo {
+            type_name: any::type_name::<A>(),
+            remote: stage.remote.clone(),
+        });
+    }
+
+    /// Returns the number of actors in the system that haven't stopped yet.
+    pub fn len(&self) -> usize {
+        self.lock().iter().filter(|info| info.is_live()).count()
+    }
+
+    /// Returns `true` if all of the actors in the system have stopped.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns information about each actor in the system that hasn't stopped yet, in
+    /// the order they were spawned.
+    pub fn actors(&self) -> Vec<ActorInfo> {
+        self.lock()
+            .iter()
+            .filter(|info| info.is_live())
+            .cloned()
+            .collect()
+    }
+
+    /// Stops every actor in the system, waiting for each one to finish running.
+    ///
+    /// Actors are stopped one at a time, in the reverse of the order they were spawned
+    /// in. Each actor handles any messages remaining in its mailbox before it stops, as
+    /// with [`Remote::stop`]. Actors that haven't started running yet are stopped as
+    /// soon as they start.
+    ///
+    /// [`Remote::stop`]: struct.Remote.html#method.stop
+    pub async fn shutdown(&self) {
+        let actors = self.actors();
+        for info in actors.into_iter().rev() {
+            info.remote.request_stop();
+            info.stopped().await;
+        }
+
+        self.lock().retain(ActorInfo::is_live);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<ActorInfo>> {
+        self.actors.lock().expect("Actor system lock poisoned")
+    }
+}
+
+/// Information about an actor tracked by an [`ActorSystem`].
+///
+/// [`ActorSystem`]: struct.ActorSystem.html
+#[derive(Debug, Clone)]
+pub struct ActorInfo {
+    type_name: &'static str,
+    remote: Arc<RemoteInner>,
+}
+
+impl ActorInfo {
+    /// Returns the name of the actor's type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the name the actor is registered under, if any.
+    pub fn name(&self) -> Option<String> {
+        self.remote.name()
+    }
+
+    pub fn state(&self) -> ActorState {
+        self.remote.state()
+    }
+
+    /// Returns a future that resolves once the actor has finished running.
+    pub fn stopped(&self) -> JoinHandle {
+        JoinHandle::new(self.remote.clone())
+    }
+
+    fn is_live(&self) -> bool {
+        !matches!(self.state(), ActorState::Stopped | ActorState::Failed)
+    }
+}
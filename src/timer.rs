@@ -0,0 +1,144 @@
+//! Timers for delivering messages to an actor after a delay.
+//!
+//! Timers scheduled through an actor's [`Context`] are run by the actor's stage, and
+//! timers scheduled through a proxy only hold onto the actor's mailbox. In either case
+//! a pending timer doesn't keep the actor alive, so an actor can schedule messages to
+//! itself without having to worry about never being stopped.
+//!
+//! [`Context`]: struct.Context.html
+
+use crate::{context::Task, runtime, Actor, Context, ErasedMessage};
+use futures::{
+    future::{AbortHandle, AbortRegistration, Abortable, BoxFuture},
+    prelude::*,
+    stream,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// A handle to a timer, which can be used to cancel the timer before it fires.
+///
+/// Dropping the handle does not cancel the timer.
+#[derive(Debug, Clone)]
+pub struct TimerHandle {
+    abort: AbortHandle,
+}
+
+impl TimerHandle {
+    pub(crate) fn new() -> (Self, AbortRegistration) {
+        let (abort, registration) = AbortHandle::new_pair();
+        (Self { abort }, registration)
+    }
+
+    /// Cancels the timer.
+    ///
+    /// If the timer has already fired, this has no effect. For timers that fire
+    /// repeatedly, no further messages will be delivered after the timer is cancelled,
+    /// including any that have fired but not yet been handled.
+    pub fn cancel(&self) {
+        self.abort.abort();
+    }
+
+    /// Returns `true` if the timer has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.abort.is_aborted()
+    }
+}
+
+/// Creates a task that produces a message for the actor once `duration` has elapsed.
+pub(crate) fn later<A, F>(duration: Duration, f: F) -> (Task<A>, TimerHandle)
+where
+    A: Actor,
+    F: FnOnce(&mut A, &mut Context<A>) + Send + 'static,
+{
+    let (timer, registration) = TimerHandle::new();
+    let message: Box<dyn ErasedMessage<A>> = Box::new(Deferred {
+        f,
+        timer: timer.clone(),
+    });
+    let task = stream::once(runtime::delay(duration).map(move |_| message));
+    (Abortable::new(task, registration).boxed(), timer)
+}
+
+/// Creates a task that produces a message for the actor each time `period` elapses.
+pub(crate) fn interval<A, F>(period: Duration, f: F) -> (Task<A>, TimerHandle)
+where
+    A: Actor,
+    F: FnMut(&mut A, &mut Context<A>) + Send + 'static,
+{
+    let (timer, registration) = TimerHandle::new();
+    let f = Arc::new(Mutex::new(f));
+    let message_timer = timer.clone();
+    let task = stream::unfold((), move |()| runtime::delay(period).map(|_| Some(((), ())))).map(
+        move |()| {
+            Box::new(Tick {
+                f: f.clone(),
+                timer: message_timer.clone(),
+            }) as Box<dyn ErasedMessage<A>>
+        },
+    );
+    (Abortable::new(task, registration).boxed(), timer)
+}
+
+/// The message produced by a timer that only fires once.
+struct Deferred<F> {
+    f: F,
+    timer: TimerHandle,
+}
+
+impl<A, F> ErasedMessage<A> for Deferred<F>
+where
+    A: Actor,
+    F: FnOnce(&mut A, &mut Context<A>) + Send + 'static,
+{
+    fn handle<'a>(self: Box<Self>, actor: &'a mut A, ctx: &'a mut Context<A>) -> BoxFuture<'a, ()> {
+        if !self.timer.is_cancelled() {
+            (self.f)(actor, ctx);
+        }
+        future::ready(()).boxed()
+    }
+
+    fn handle_shared<'a>(
+        self: Box<Self>,
+        _actor: &'a A,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<A>>>
+    where
+        for<'x> &'x A: Send,
+    {
+        Err(self)
+    }
+}
+
+/// The message produced each time a repeating timer fires.
+struct Tick<F> {
+    // NOTE: The closure is shared between all of the messages produced by the timer, but
+    // since the messages are handled one at a time the lock is never contended.
+    f: Arc<Mutex<F>>,
+    timer: TimerHandle,
+}
+
+impl<A, F> ErasedMessage<A> for Tick<F>
+where
+    A: Actor,
+    F: FnMut(&mut A, &mut Context<A>) + Send + 'static,
+{
+    fn handle<'a>(self: Box<Self>, actor: &'a mut A, ctx: &'a mut Context<A>) -> BoxFuture<'a, ()> {
+        if !self.timer.is_cancelled() {
+            let mut f = self.f.lock().unwrap();
+            (*f)(actor, ctx);
+        }
+        future::ready(()).boxed()
+    }
+
+    fn handle_shared<'a>(
+        self: Box<Self>,
+        _actor: &'a A,
+    ) -> Result<BoxFuture<'a, ()>, Box<dyn ErasedMessage<A>>>
+    where
+        for<'x> &'x A: Send,
+    {
+        Err(self)
+    }
+}
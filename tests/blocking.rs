@@ -0,0 +1,59 @@
+//! Tests verifying that proxies can be used from synchronous code by blocking the
+//! current thread, and that doing so from within an actor is caught.
+
+#![allow(unused_imports)]
+
+use futures::executor;
+use std::thread;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Counter {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Counter {
+    pub fn add(&mut self, value: usize) {
+        self.value += value;
+    }
+
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    pub fn value_of(&self, other: CounterProxy) -> std::result::Result<usize, RequestError> {
+        other.value_blocking()
+    }
+}
+
+/// Runs the actor on its own thread, without an async runtime.
+fn spawn_on_thread(counter: Counter) -> CounterProxy {
+    let stage = counter.into_stage();
+    let proxy = stage.proxy();
+    thread::spawn(move || executor::block_on(stage.run()));
+    proxy
+}
+
+#[test]
+fn send_from_thread() {
+    let counter = spawn_on_thread(Counter::default());
+
+    counter.add_blocking(2).unwrap();
+    counter.add_blocking(3).unwrap();
+    assert_eq!(5, counter.value_blocking().unwrap());
+}
+
+#[test]
+fn blocking_in_handler() {
+    let counter = spawn_on_thread(Counter::default());
+    let other = spawn_on_thread(Counter { value: 1 });
+
+    // Blocking within the handler panics rather than stalling the actor's thread.
+    match counter.value_of_blocking(other) {
+        Err(RequestError::Panicked(message)) => {
+            assert!(message.contains("can't be used from within an actor"))
+        }
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
@@ -0,0 +1,46 @@
+//! Tests verifying that the actor value can be recovered once the stage has finished
+//! running.
+
+use futures::executor::block_on;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Counter {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Counter {
+    pub fn add(&mut self, value: usize) {
+        self.value += value;
+    }
+
+    pub fn explode(&mut self) {
+        panic!("Counter exploded");
+    }
+}
+
+#[test]
+fn recover_actor() {
+    let stage = Counter::default().into_stage();
+    let counter = stage.proxy();
+    for value in 1..=3 {
+        counter.add(value).unwrap();
+    }
+
+    // Drop the proxy so that the actor stops once it has handled the queued messages.
+    drop(counter);
+
+    let counter = block_on(stage.run_to_completion()).unwrap();
+    assert_eq!(6, counter.value);
+}
+
+#[test]
+fn panicked_actor_not_recovered() {
+    let stage = Counter::default().into_stage();
+    let counter = stage.proxy();
+    counter.explode().unwrap();
+    drop(counter);
+
+    assert!(block_on(stage.run_to_completion()).is_none());
+}
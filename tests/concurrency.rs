@@ -1,6 +1,7 @@
 #![allow(unused_imports)]
 
 use futures::future;
+use std::sync::Arc;
 use thespian::*;
 
 #[derive(Debug, Default, Actor)]
@@ -27,20 +28,42 @@ impl Counter {
 #[tokio::test]
 async fn multiple_tasks() {
     // Spawn the actor as a concurrent task.
-    let mut actor = Counter::default().spawn();
+    let actor = Counter::default().spawn();
 
     // Spawn 10 tasks, each of which will add 10 to the actor's value.
     let mut tasks = Vec::new();
     for _ in 0..10 {
-        let mut actor = actor.clone();
+        let actor = actor.clone();
         let join_handle = tokio::spawn(async move {
             for _ in 0..10 {
-                actor.add(1).unwrap().await;
+                actor.add(1).unwrap().await.unwrap();
             }
         });
         tasks.push(join_handle);
     }
 
     future::join_all(tasks).await;
-    assert_eq!(100, actor.value().unwrap().await);
+    assert_eq!(100, actor.value().unwrap().await.unwrap());
+}
+
+// Test sharing a single proxy between multiple tasks, without cloning it for each
+// task.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn shared_proxy() {
+    let actor = Arc::new(Counter::default().spawn());
+
+    let mut tasks = Vec::new();
+    for _ in 0..10 {
+        let actor = actor.clone();
+        let join_handle = tokio::spawn(async move {
+            for _ in 0..10 {
+                actor.add(1).unwrap().await.unwrap();
+            }
+        });
+        tasks.push(join_handle);
+    }
+
+    future::join_all(tasks).await;
+    assert_eq!(100, actor.value().unwrap().await.unwrap());
 }
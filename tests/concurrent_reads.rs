@@ -0,0 +1,79 @@
+//! Tests verifying that actors can opt into handling read-only messages concurrently,
+//! while messages that modify the actor still get exclusive access.
+
+#![allow(unused_imports)]
+
+use futures::{channel::oneshot, prelude::*};
+use std::{sync::Arc, time::Duration};
+use thespian::*;
+
+#[derive(Debug, Actor)]
+#[thespian(concurrent_reads)]
+pub struct Lookup {
+    value: usize,
+    barrier: Arc<tokio::sync::Barrier>,
+}
+
+#[thespian::actor]
+impl Lookup {
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    /// Waits until the expected number of readers are waiting at the same time.
+    pub async fn meet(&self) -> usize {
+        self.barrier.wait().await;
+        self.value
+    }
+
+    pub async fn value_after(&self, gate: oneshot::Receiver<()>) -> usize {
+        gate.await.unwrap();
+        self.value
+    }
+
+    pub fn set(&mut self, value: usize) {
+        self.value = value;
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn lookup(readers: usize) -> Lookup {
+    Lookup {
+        value: 0,
+        barrier: Arc::new(tokio::sync::Barrier::new(readers)),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn reads_run_concurrently() {
+    let actor = lookup(3).spawn();
+
+    // Each read waits for the others to arrive, so they can only complete if they're all
+    // running at the same time.
+    let reads = future::try_join_all((0..3).map(|_| actor.meet().unwrap()));
+    let values = tokio::time::timeout(Duration::from_secs(5), reads)
+        .await
+        .expect("Reads weren't handled concurrently")
+        .unwrap();
+    assert_eq!(vec![0, 0, 0], values);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn writes_are_exclusive() {
+    let actor = lookup(1).spawn();
+
+    let (open, gate) = oneshot::channel();
+    let first = actor.value_after(gate).unwrap();
+    actor.set(5).unwrap();
+    let second = actor.value().unwrap();
+    futures::pin_mut!(second);
+
+    // The write waits for the running read to complete, and the read queued after the
+    // write waits for the write.
+    assert!(futures::poll!(&mut second).is_pending());
+    open.send(()).unwrap();
+    assert_eq!(0, first.await.unwrap());
+    assert_eq!(5, second.await.unwrap());
+}
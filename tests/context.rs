@@ -0,0 +1,114 @@
+//! Tests verifying that message handlers can use the actor's `Context` to access their
+//! own stage.
+
+#![allow(unused_imports)]
+
+use futures::{channel::oneshot, prelude::*};
+use std::time::Duration;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Worker {
+    events: Vec<&'static str>,
+}
+
+#[thespian::actor]
+impl Worker {
+    pub fn record(&mut self, event: &'static str) {
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> Vec<&'static str> {
+        self.events.clone()
+    }
+
+    // The context can appear anywhere in the argument list, and can be used across
+    // await points.
+    pub async fn record_state(
+        &mut self,
+        ctx: &mut Context<Self>,
+        event: &'static str,
+    ) -> ActorState {
+        self.events.push(event);
+        future::ready(()).await;
+        ctx.state()
+    }
+
+    pub fn finish(&mut self, ctx: &mut Context<Self>) {
+        self.events.push("finish");
+        ctx.stop();
+    }
+
+    pub fn spawn_record(&mut self, ctx: &mut Context<Self>, done: oneshot::Sender<()>) {
+        let proxy = ctx.proxy();
+        ctx.spawn(async move {
+            proxy.record("spawned").unwrap();
+            let _ = done.send(());
+        });
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[thespian::actor]
+impl Worker {
+    pub fn record_later(
+        &mut self,
+        delay: Duration,
+        ctx: &mut Context<Self>,
+        done: oneshot::Sender<()>,
+    ) {
+        ctx.run_later(delay, |worker, _| {
+            worker.events.push("later");
+            let _ = done.send(());
+        });
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stop_from_handler() {
+    let (worker, handle) = Worker::default().spawn_with_handle();
+
+    let state = worker.record_state("state").unwrap();
+    assert_eq!(ActorState::Running, state.await.unwrap());
+
+    worker.finish().unwrap();
+    assert_eq!(ExitReason::Stopped, handle.await);
+    assert!(worker.record("ignored").is_err());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn spawn_with_proxy() {
+    let worker = Worker::default().spawn();
+
+    // Wait for each task to signal that it has run before moving on, so that the events
+    // are recorded in a known order.
+    let (done, spawned) = oneshot::channel();
+    worker.spawn_record(done).unwrap();
+    spawned.await.unwrap();
+
+    let (done, later) = oneshot::channel();
+    worker
+        .record_later(Duration::from_millis(10), done)
+        .unwrap();
+    later.await.unwrap();
+
+    let events = worker.events().unwrap().await.unwrap();
+    assert_eq!(vec!["spawned", "later"], events);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn tasks_dropped_on_stop() {
+    let (worker, handle) = Worker::default().spawn_with_handle();
+
+    let (done, _later) = oneshot::channel();
+    worker
+        .record_later(Duration::from_millis(10), done)
+        .unwrap();
+    drop(worker);
+
+    // The pending timer doesn't keep the actor alive.
+    assert_eq!(ExitReason::ProxiesDropped, handle.await);
+}
@@ -0,0 +1,113 @@
+//! Tests verifying that messages that can't be delivered to an actor are passed to
+//! the configured dead letter handler.
+
+#![allow(unused_imports)]
+
+use std::sync::{Arc, Mutex};
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Counter {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Counter {
+    pub fn add(&mut self, value: usize) {
+        self.value += value;
+    }
+
+    pub fn value(&self) -> usize {
+        self.value
+    }
+}
+
+type Letters = Arc<Mutex<Vec<(ActorId, &'static str, DeadLetterReason)>>>;
+
+fn record(letters: &Letters) -> impl Fn(DeadLetter) + Send + Sync + 'static {
+    let letters = letters.clone();
+    move |letter| {
+        letters
+            .lock()
+            .unwrap()
+            .push((letter.actor(), letter.message_type(), letter.reason()));
+    }
+}
+
+#[test]
+fn rejected_and_discarded() {
+    let letters = Letters::default();
+    let (builder, _) = StageBuilder::new();
+    let stage = builder
+        .capacity(1)
+        .dead_letters(record(&letters))
+        .finish(Counter::default());
+    let id = stage.id();
+    let counter = stage.proxy();
+
+    counter.add(1).unwrap();
+    counter.add(2).unwrap_err();
+
+    // Dropping the stage without running it discards the queued message, after which
+    // the actor can no longer receive messages.
+    drop(stage);
+    counter.add(3).unwrap_err();
+
+    assert_eq!(
+        vec![
+            (id, "Counter::add", DeadLetterReason::MailboxFull),
+            (id, "Counter::add", DeadLetterReason::Discarded),
+            (id, "Counter::add", DeadLetterReason::ActorStopped),
+        ],
+        *letters.lock().unwrap(),
+    );
+}
+
+#[test]
+fn global_handler() {
+    let letters = Letters::default();
+    let stage = Counter::default().into_stage();
+    let id = stage.id();
+    let counter = stage.proxy();
+
+    // Other tests may run at the same time, so only record dead letters for this actor.
+    let record = record(&letters);
+    set_dead_letter_handler(move |letter| {
+        if letter.actor() == id {
+            record(letter);
+        }
+    });
+
+    drop(stage);
+    counter.add(1).unwrap_err();
+
+    // Remove the handler so that it doesn't affect later tests.
+    clear_dead_letter_handler();
+    counter.add(2).unwrap_err();
+
+    assert_eq!(
+        vec![(id, "Counter::add", DeadLetterReason::ActorStopped)],
+        *letters.lock().unwrap(),
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn redeliver_displaced_request() {
+    let backup = Counter { value: 5 }.spawn();
+
+    let (builder, _) = StageBuilder::new();
+    let handler_backup = backup.clone();
+    let stage = builder
+        .capacity(1)
+        .overflow_policy(OverflowPolicy::DropOldest)
+        .dead_letters(move |letter| letter.redeliver(&handler_backup).unwrap())
+        .finish(Counter::default());
+    let counter = stage.proxy();
+
+    // The request is displaced from the full mailbox and handled by the backup instead,
+    // which responds to the original requester.
+    let value = counter.value().unwrap();
+    counter.add(1).unwrap();
+    assert_eq!(5, value.await.unwrap());
+}
@@ -10,7 +10,7 @@
 //!
 //! [#9]: https://github.com/randomPoison/thespian/issues/9
 
-#![allow(unused_imports, clippy::blacklisted_name)]
+#![allow(unused_imports, clippy::disallowed_names)]
 
 use futures::{channel::oneshot, future};
 use std::time::Duration;
@@ -25,7 +25,7 @@ pub struct Foo {
 #[thespian::actor]
 impl Foo {
     pub async fn tell_bar(&mut self) {
-        self.bar.add_to_foo().unwrap().await;
+        self.bar.add_to_foo().unwrap().await.unwrap();
     }
 
     pub fn add(&mut self, value: usize) {
@@ -68,7 +68,7 @@ async fn possible_deadlock() {
     });
 
     // Send the initial message to `Foo` to start the potential deadlock.
-    let mut foo = foo_remote.proxy();
+    let foo = foo_remote.proxy();
     foo.tell_bar().unwrap();
 
     // Request the updated value from `foo`. If the actors have deadlocked the timeout
@@ -0,0 +1,143 @@
+//! Tests verifying that generic types can be used as actors.
+
+#![allow(unused_imports)]
+
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
+use thespian::*;
+
+#[derive(Debug, Actor)]
+pub struct Cache<K, V>
+where
+    K: Eq + Hash + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    entries: HashMap<K, V>,
+}
+
+#[thespian::actor]
+impl<K, V> Cache<K, V>
+where
+    K: Eq + Hash + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    pub fn insert(&mut self, key: K, value: V) {
+        self.entries.insert(key, value);
+    }
+
+    pub fn get(&self, key: K) -> Option<V> {
+        self.entries.get(&key).cloned()
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub async fn remove(&mut self, key: K) -> Option<V> {
+        self.entries.remove(&key)
+    }
+}
+
+// Test that message handlers can be defined for a specific instantiation of a generic
+// actor.
+#[thespian::actor]
+impl Cache<String, usize> {
+    pub fn total(&self) -> usize {
+        self.entries.values().sum()
+    }
+}
+
+// Test that impl blocks for different instantiations of a generic actor can define
+// methods with the same name.
+#[derive(Debug, Actor)]
+pub struct Slot<T: Send + 'static> {
+    value: T,
+}
+
+#[thespian::actor]
+impl Slot<u32> {
+    pub fn describe(&self) -> String {
+        format!("u32: {}", self.value)
+    }
+}
+
+#[thespian::actor]
+impl Slot<u64> {
+    pub fn describe(&self) -> String {
+        format!("u64: {}", self.value)
+    }
+}
+
+#[thespian::actor]
+impl Slot<&'static str> {
+    pub fn describe(&self) -> String {
+        format!("str: {}", self.value)
+    }
+}
+
+// Test that the type parameters don't need to implement `Clone` or `Debug` for the
+// proxy type to implement them.
+#[derive(Actor)]
+pub struct Holder<T: Send + 'static> {
+    value: Option<T>,
+}
+
+#[thespian::actor]
+impl<T: Send + 'static> Holder<T> {
+    pub fn take(&mut self) -> Option<T> {
+        self.value.take()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NotClone(usize);
+
+fn assert_proxy<P: Clone + Debug>(_: &P) {}
+
+#[test]
+fn proxy_impls() {
+    let stage = Holder {
+        value: Some(NotClone(1)),
+    }
+    .into_stage();
+    assert_proxy(&stage.proxy());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn generic_actor() {
+    let cache = Cache {
+        entries: HashMap::new(),
+    }
+    .spawn();
+
+    cache.insert("first".to_string(), 1).unwrap();
+    cache.insert("second".to_string(), 2).unwrap();
+
+    assert_eq!(Some(2), cache.get("second".into()).unwrap().await.unwrap());
+    assert_eq!(None, cache.get("third".into()).unwrap().await.unwrap());
+    assert_eq!(3, cache.total().unwrap().await.unwrap());
+
+    assert_eq!(
+        Some(1),
+        cache.remove("first".into()).unwrap().await.unwrap()
+    );
+    assert_eq!(1, cache.count().unwrap().await.unwrap());
+
+    let holder = Holder {
+        value: Some(NotClone(7)),
+    }
+    .spawn();
+    assert_eq!(Some(NotClone(7)), holder.take().unwrap().await.unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn methods_per_instantiation() {
+    let small = Slot { value: 1u32 }.spawn();
+    let large = Slot { value: 2u64 }.spawn();
+    let text = Slot { value: "three" }.spawn();
+
+    assert_eq!("u32: 1", small.describe().unwrap().await.unwrap());
+    assert_eq!("u64: 2", large.describe().unwrap().await.unwrap());
+    assert_eq!("str: three", text.describe().unwrap().await.unwrap());
+}
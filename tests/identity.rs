@@ -0,0 +1,54 @@
+//! Tests verifying that each actor is assigned a unique ID, which can be used to tell
+//! whether two proxies refer to the same actor.
+
+#![allow(unused_imports)]
+
+use std::collections::HashSet;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Counter;
+
+#[thespian::actor]
+impl Counter {
+    pub fn own_id(&self, ctx: &mut Context<Self>) -> ActorId {
+        ctx.id()
+    }
+}
+
+#[test]
+fn same_id_everywhere() {
+    let (builder, remote) = StageBuilder::new();
+    let id = builder.id();
+    let stage = builder.finish(Counter);
+
+    assert_eq!(id, remote.id());
+    assert_eq!(id, stage.id());
+    assert_eq!(id, stage.proxy().id());
+    assert_eq!(id, remote.proxy().id());
+    assert_eq!(stage.proxy(), remote.proxy());
+}
+
+#[test]
+fn proxies_as_keys() {
+    let first = Counter.into_stage();
+    let second = Counter.into_stage();
+    assert_ne!(first.id(), second.id());
+    assert_ne!(first.proxy(), second.proxy());
+
+    // NOTE: Proxies are only hashed by the actor's ID, which never changes, so the
+    // interior mutability of the mailbox doesn't affect their hash.
+    #[allow(clippy::mutable_key_type)]
+    let proxies: HashSet<CounterProxy> = vec![first.proxy(), second.proxy(), first.proxy()]
+        .into_iter()
+        .collect();
+    assert_eq!(2, proxies.len());
+    assert!(proxies.contains(&first.proxy()));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn id_from_context() {
+    let proxy = Counter.spawn();
+    assert_eq!(proxy.id(), proxy.own_id().unwrap().await.unwrap());
+}
@@ -0,0 +1,85 @@
+//! Tests verifying that actors can implement an interface trait, allowing callers to
+//! depend on the proxy-side trait rather than a concrete proxy type.
+
+#![allow(unused_imports)]
+
+use std::collections::HashMap;
+use thespian::*;
+
+#[thespian::interface]
+pub trait KeyValueStore {
+    /// Stores `value` under `key`.
+    fn set(&mut self, key: String, value: String);
+
+    fn get(&self, key: String) -> Option<String>;
+}
+
+#[derive(Debug, Default, Actor)]
+pub struct MemoryStore {
+    entries: HashMap<String, String>,
+}
+
+#[thespian::actor]
+impl KeyValueStore for MemoryStore {
+    fn set(&mut self, key: String, value: String) {
+        self.entries.insert(key, value);
+    }
+
+    fn get(&self, key: String) -> Option<String> {
+        self.entries.get(&key).cloned()
+    }
+}
+
+// Test that an actor can have inherent message handlers alongside an interface.
+#[thespian::actor]
+impl MemoryStore {
+    pub fn get(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// A store that ignores writes and always returns the same value.
+#[derive(Debug, Actor)]
+pub struct ConstantStore {
+    value: String,
+}
+
+#[thespian::actor]
+impl KeyValueStore for ConstantStore {
+    fn set(&mut self, _key: String, _value: String) {}
+
+    fn get(&self, _key: String) -> Option<String> {
+        Some(self.value.clone())
+    }
+}
+
+#[cfg(feature = "tokio")]
+async fn roundtrip(store: &dyn KeyValueStoreProxy) -> Option<String> {
+    store.set("key".into(), "value".into()).unwrap();
+    store.get_wait("key".into()).await.unwrap().await.unwrap()
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn swap_implementations() {
+    let memory = MemoryStore::default().spawn();
+    let constant = ConstantStore {
+        value: "constant".into(),
+    }
+    .spawn();
+
+    let stores: Vec<Box<dyn KeyValueStoreProxy>> =
+        vec![Box::new(memory.clone()), Box::new(constant)];
+    let mut values = Vec::new();
+    for store in &stores {
+        values.push(roundtrip(&**store).await);
+    }
+
+    assert_eq!(
+        vec![Some("value".to_string()), Some("constant".to_string())],
+        values,
+    );
+
+    // The inherent method doesn't conflict with the interface method of the same name.
+    assert_eq!(1, memory.get().unwrap().await.unwrap());
+}
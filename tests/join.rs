@@ -0,0 +1,83 @@
+//! Tests verifying that a `JoinHandle` resolves once the actor has finished running,
+//! yielding the reason the actor stopped.
+
+#![allow(unused_imports)]
+
+use std::time::Duration;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Worker {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Worker {
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    pub fn explode(&mut self) {
+        panic!("Worker exploded");
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stopped() {
+    let (builder, remote) = StageBuilder::new();
+    let (worker, handle) = builder.spawn_with_handle(Worker::default());
+
+    // Wait for the actor to start running, since it can't be stopped before then.
+    worker.value().unwrap().await.unwrap();
+
+    // The actor is idle, so it must be stopped without needing to receive a message.
+    remote.stop().unwrap();
+    assert_eq!(ExitReason::Stopped, handle.await);
+    assert_eq!(ActorState::Stopped, remote.state());
+    assert_eq!(ExitReason::Stopped, remote.stopped().await);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn proxies_dropped() {
+    let (worker, handle) = Worker::default().spawn_with_handle();
+    drop(worker);
+    assert_eq!(ExitReason::ProxiesDropped, handle.await);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn panicked() {
+    let (worker, handle) = Worker::default().spawn_with_handle();
+    worker.explode().unwrap();
+    assert_eq!(ExitReason::Panicked("Worker exploded".into()), handle.await);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stage_dropped() {
+    let stage = Worker::default().into_stage();
+
+    // Make sure the handle is already waiting when the stage is dropped.
+    let mut handle = stage.join_handle();
+    assert!(futures::poll!(&mut handle).is_pending());
+
+    drop(stage);
+    assert_eq!(ExitReason::Dropped, handle.await);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn run_cancelled() {
+    let stage = Worker::default().into_stage();
+    let handle = stage.join_handle();
+    let worker = stage.proxy();
+
+    // The actor never stops on its own while a proxy is held, so the run future is
+    // dropped mid-flight once the timeout elapses.
+    let run = tokio::time::timeout(Duration::from_millis(10), stage.run());
+    run.await.unwrap_err();
+    assert_eq!(ExitReason::Dropped, handle.await);
+    worker.value().err().unwrap();
+}
@@ -0,0 +1,72 @@
+//! Tests verifying that the actor lifecycle hooks are invoked at the expected points
+//! while the actor is running.
+
+#![allow(unused_imports)]
+
+use futures::{
+    channel::{mpsc, oneshot},
+    future::BoxFuture,
+    prelude::*,
+};
+use thespian::*;
+
+#[derive(Debug, Actor)]
+#[thespian(proxy_only)]
+pub struct Lifecycle {
+    events: mpsc::UnboundedSender<&'static str>,
+    done: Option<oneshot::Sender<()>>,
+}
+
+impl Actor for Lifecycle {
+    type Proxy = LifecycleProxy;
+
+    fn started(&mut self) -> BoxFuture<'_, ()> {
+        self.events.unbounded_send("started").unwrap();
+        future::ready(()).boxed()
+    }
+
+    fn stopping(&mut self) -> BoxFuture<'_, ()> {
+        self.events.unbounded_send("stopping").unwrap();
+        future::ready(()).boxed()
+    }
+
+    fn stopped(&mut self) -> BoxFuture<'_, ()> {
+        async move {
+            self.events.unbounded_send("stopped").unwrap();
+            self.done.take().unwrap().send(()).unwrap();
+        }
+        .boxed()
+    }
+}
+
+#[thespian::actor]
+impl Lifecycle {
+    pub fn ping(&mut self) {
+        self.events.unbounded_send("ping").unwrap();
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn hooks_called_in_order() {
+    let (events, mut receiver) = mpsc::unbounded();
+    let (done, stopped) = oneshot::channel();
+
+    let actor = Lifecycle {
+        events,
+        done: Some(done),
+    }
+    .spawn();
+    actor.ping().unwrap();
+
+    // Dropping the last proxy stops the actor.
+    drop(actor);
+    stopped.await.unwrap();
+
+    let mut actual = Vec::new();
+    while let Some(event) = receiver.next().await {
+        actual.push(event);
+    }
+
+    assert_eq!(vec!["started", "ping", "stopping", "stopped"], actual);
+}
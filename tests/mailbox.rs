@@ -0,0 +1,155 @@
+//! Tests verifying the mailbox capacity and overflow policy options on
+//! `StageBuilder`.
+
+#![allow(unused_imports)]
+
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Counter {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Counter {
+    pub fn add(&mut self, value: usize) {
+        self.value += value;
+    }
+
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    pub fn add_all(&mut self, values: Vec<usize>, scale: usize) -> usize {
+        self.value += values.iter().sum::<usize>() * scale;
+        self.value
+    }
+}
+
+#[test]
+fn reject_newest() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder.capacity(2).finish(Counter::default());
+    let counter = stage.proxy();
+
+    counter.add(1).unwrap();
+    counter.add(1).unwrap();
+
+    let error = counter.add(1).unwrap_err();
+    assert!(matches!(error.cause(), MessageErrorCause::MailboxFull));
+}
+
+#[test]
+fn rejected_message_returned() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder.capacity(1).finish(Counter::default());
+    let counter = stage.proxy();
+    counter.add(1).unwrap();
+
+    let error = counter.add(2).unwrap_err();
+    assert!(matches!(error.cause(), MessageErrorCause::MailboxFull));
+    assert_eq!(2, error.into_inner());
+
+    // Methods with several arguments hand them back as a tuple.
+    let error = counter.add_all(vec![1, 2, 3], 2).err().unwrap();
+    assert_eq!((vec![1, 2, 3], 2), error.into_inner());
+}
+
+#[test]
+fn unbounded() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder.unbounded().finish(Counter::default());
+    let counter = stage.proxy();
+
+    for _ in 0..100 {
+        counter.add(1).unwrap();
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn drop_oldest() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder
+        .capacity(1)
+        .overflow_policy(OverflowPolicy::DropOldest)
+        .finish(Counter::default());
+    let counter = stage.proxy();
+
+    // Sending the second message displaces the request from the mailbox.
+    let value = counter.value().unwrap();
+    counter.add(1).unwrap();
+    assert!(matches!(value.await, Err(RequestError::Dropped)));
+}
+
+// NOTE: `#[tokio::test]` uses a single-threaded runtime, so the actor can only make
+// progress if sending to the full mailbox doesn't block the thread.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn block() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder
+        .capacity(1)
+        .overflow_policy(OverflowPolicy::Block)
+        .finish(Counter::default());
+    let counter = stage.proxy();
+    counter.add(1).unwrap();
+
+    // The mailbox is full, so the request waits for space instead of failing.
+    let value = counter.value().unwrap();
+    counter.add(2).unwrap();
+    futures::pin_mut!(value);
+    assert!(futures::poll!(&mut value).is_pending());
+
+    // Once the actor takes the first message out of its mailbox, the waiting messages
+    // are delivered in the order they were sent.
+    tokio::spawn(stage.run());
+    assert_eq!(1, value.await.unwrap());
+    assert_eq!(3, counter.value().unwrap().await.unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn wait_for_space() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder.capacity(1).finish(Counter::default());
+    let counter = stage.proxy();
+    counter.add(1).unwrap();
+
+    // The mailbox is full, so the send waits until the actor starts running.
+    let waiting = counter.clone();
+    let send = async move { waiting.add_wait(2).await };
+    futures::pin_mut!(send);
+    assert!(futures::poll!(&mut send).is_pending());
+
+    tokio::spawn(stage.run());
+    send.await.unwrap();
+
+    let value = counter.value_wait().await.unwrap();
+    assert_eq!(3, value.await.unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn cancelled_wait() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder.capacity(1).finish(Counter::default());
+    let counter = stage.proxy();
+    counter.add(1).unwrap();
+
+    let first = counter.clone();
+    let mut first = Box::pin(async move { first.add_wait(2).await });
+    let second = counter.clone();
+    let second = async move { second.add_wait(3).await };
+    futures::pin_mut!(second);
+    assert!(futures::poll!(&mut first).is_pending());
+    assert!(futures::poll!(&mut second).is_pending());
+
+    // Cancelling the send at the front of the line lets the next one take its place.
+    drop(first);
+    tokio::spawn(stage.run());
+    second.await.unwrap();
+
+    let value = counter.value_wait().await.unwrap();
+    assert_eq!(4, value.await.unwrap());
+}
@@ -30,10 +30,10 @@ impl MyActor {
 #[cfg(feature = "tokio")]
 #[tokio::test]
 async fn test_actor_impl() {
-    let mut actor = MyActor::default().spawn();
+    let actor = MyActor::default().spawn();
 
     for value in 1..10 {
-        let result = actor.add_sync(1).unwrap().await;
+        let result = actor.add_sync(1).unwrap().await.unwrap();
         assert_eq!(value, result);
     }
 }
@@ -52,20 +52,47 @@ pub struct MyActorProxy {
 }
 
 impl MyActorProxy {
-    pub fn value(&mut self) -> thespian::Result<impl Future<Output = usize>> {
-        self.inner.send_request(MyActor_value())
+    pub fn value(
+        &self,
+    ) -> std::result::Result<
+        impl Future<Output = std::result::Result<usize, RequestError>>,
+        SendError<()>,
+    > {
+        self.inner
+            .send_request(MyActor_value())
+            .map_err(|error| error.map_message(|MyActor_value()| (), |()| MyActor_value()))
     }
 
-    pub fn add_sync(&mut self, value: usize) -> thespian::Result<impl Future<Output = usize>> {
-        self.inner.send_request(MyActor__add_sync(value))
+    pub fn add_sync(
+        &self,
+        value: usize,
+    ) -> std::result::Result<
+        impl Future<Output = std::result::Result<usize, RequestError>>,
+        SendError<usize>,
+    > {
+        self.inner
+            .send_request(MyActor__add_sync(value))
+            .map_err(|error| error.map_message(|MyActor__add_sync(value)| value, MyActor__add_sync))
     }
 
-    pub fn add_async(&mut self, value: usize) -> thespian::Result<impl Future<Output = usize>> {
-        self.inner.send_request(MyActor__add_async(value))
+    pub fn add_async(
+        &self,
+        value: usize,
+    ) -> std::result::Result<
+        impl Future<Output = std::result::Result<usize, RequestError>>,
+        SendError<usize>,
+    > {
+        self.inner
+            .send_request(MyActor__add_async(value))
+            .map_err(|error| {
+                error.map_message(|MyActor__add_async(value)| value, MyActor__add_async)
+            })
     }
 
-    pub fn add(&mut self, value: usize) -> thespian::Result<()> {
-        self.inner.send_message(MyActor__add(value))
+    pub fn add(&self, value: usize) -> std::result::Result<(), SendError<usize>> {
+        self.inner
+            .send_message(MyActor__add(value))
+            .map_err(|error| error.map_message(|MyActor__add(value)| value, MyActor__add))
     }
 }
 
@@ -75,6 +102,10 @@ impl ActorProxy for MyActorProxy {
     fn new(inner: ProxyFor<MyActor>) -> Self {
         MyActorProxy { inner }
     }
+
+    fn inner(&self) -> &ProxyFor<MyActor> {
+        &self.inner
+    }
 }
 
 #[derive(Debug)]
@@ -85,7 +116,11 @@ impl Message for MyActor_value {
     type Actor = MyActor;
     type Output = usize;
 
-    fn handle(self, actor: &mut Self::Actor) -> BoxFuture<'_, Self::Output> {
+    fn handle<'a>(
+        self,
+        actor: &'a mut Self::Actor,
+        _: &'a mut Context<Self::Actor>,
+    ) -> BoxFuture<'a, Self::Output> {
         async move { actor.value() }.boxed()
     }
 }
@@ -98,7 +133,11 @@ impl Message for MyActor__add_sync {
     type Actor = MyActor;
     type Output = usize;
 
-    fn handle(self, actor: &mut Self::Actor) -> BoxFuture<'_, Self::Output> {
+    fn handle<'a>(
+        self,
+        actor: &'a mut Self::Actor,
+        _: &'a mut Context<Self::Actor>,
+    ) -> BoxFuture<'a, Self::Output> {
         async move { actor.add_sync(self.0) }.boxed()
     }
 }
@@ -111,7 +150,11 @@ impl Message for MyActor__add_async {
     type Actor = MyActor;
     type Output = usize;
 
-    fn handle(self, actor: &mut Self::Actor) -> BoxFuture<'_, Self::Output> {
+    fn handle<'a>(
+        self,
+        actor: &'a mut Self::Actor,
+        _: &'a mut Context<Self::Actor>,
+    ) -> BoxFuture<'a, Self::Output> {
         async move { actor.add_async(self.0).await }.boxed()
     }
 }
@@ -124,7 +167,11 @@ impl Message for MyActor__add {
     type Actor = MyActor;
     type Output = ();
 
-    fn handle(self, actor: &mut Self::Actor) -> BoxFuture<'_, ()> {
+    fn handle<'a>(
+        self,
+        actor: &'a mut Self::Actor,
+        _: &'a mut Context<Self::Actor>,
+    ) -> BoxFuture<'a, ()> {
         async move { actor.add(self.0) }.boxed()
     }
 }
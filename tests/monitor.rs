@@ -0,0 +1,151 @@
+//! Tests verifying that actors can monitor and link to other actors in order to be
+//! notified when they stop.
+
+#![allow(unused_imports)]
+
+use futures::{channel::mpsc, future::BoxFuture, prelude::*};
+use std::collections::HashMap;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Sessions {
+    names: HashMap<ActorId, String>,
+    terminated: Vec<Terminated>,
+
+    // Notified after each `Terminated` message is handled, so that tests can wait for
+    // the notifications to be delivered.
+    notify: Option<mpsc::UnboundedSender<ActorId>>,
+}
+
+#[thespian::actor]
+impl Sessions {
+    pub fn add(
+        &mut self,
+        id: ActorId,
+        name: String,
+        ctx: &mut Context<Self>,
+    ) -> std::result::Result<(), MonitorError> {
+        ctx.monitor(id)?;
+        self.names.insert(id, name);
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.names.values().cloned().collect()
+    }
+
+    pub fn terminated(&self) -> Vec<Terminated> {
+        self.terminated.clone()
+    }
+}
+
+impl Handler<Terminated> for Sessions {
+    type Output = ();
+
+    fn handle(&mut self, terminated: Terminated, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+        self.names.remove(&terminated.id);
+        if let Some(notify) = &self.notify {
+            let _ = notify.unbounded_send(terminated.id);
+        }
+        self.terminated.push(terminated);
+        future::ready(()).boxed()
+    }
+}
+
+#[derive(Debug, Default, Actor)]
+pub struct Connection;
+
+#[thespian::actor]
+impl Connection {
+    pub fn close(&mut self, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+
+    pub fn crash(&mut self) {
+        panic!("Connection reset");
+    }
+
+    pub fn link(
+        &mut self,
+        id: ActorId,
+        ctx: &mut Context<Self>,
+    ) -> std::result::Result<(), MonitorError> {
+        ctx.link(id)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn notified_on_stop_and_panic() {
+    let (notify, mut notified) = mpsc::unbounded();
+    let sessions = Sessions {
+        notify: Some(notify),
+        ..Default::default()
+    }
+    .spawn();
+    let (closed, closed_handle) = Connection.spawn_with_handle();
+    let (crashed, crashed_handle) = Connection.spawn_with_handle();
+
+    for (connection, name) in &[(&closed, "closed"), (&crashed, "crashed")] {
+        let added = sessions.add(connection.id(), name.to_string()).unwrap();
+        added.await.unwrap().unwrap();
+    }
+    let mut names = sessions.names().unwrap().await.unwrap();
+    names.sort();
+    assert_eq!(vec!["closed", "crashed"], names);
+
+    closed.close().unwrap();
+    closed_handle.await;
+    crashed.crash().unwrap();
+    crashed_handle.await;
+
+    // Wait for both notifications to be handled.
+    let mut ids = vec![
+        notified.next().await.unwrap(),
+        notified.next().await.unwrap(),
+    ];
+    ids.sort();
+    assert_eq!(vec![closed.id(), crashed.id()], ids);
+    assert!(sessions.names().unwrap().await.unwrap().is_empty());
+
+    let mut terminated = sessions.terminated().unwrap().await.unwrap();
+    terminated.sort_by_key(|terminated| terminated.id);
+    assert_eq!(
+        vec![
+            Terminated {
+                id: closed.id(),
+                reason: ExitReason::Stopped,
+            },
+            Terminated {
+                id: crashed.id(),
+                reason: ExitReason::Panicked("Connection reset".into()),
+            },
+        ],
+        terminated,
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn monitor_missing_actor() {
+    let sessions = Sessions::default().spawn();
+
+    // The stage is dropped without running, so nothing refers to the actor anymore.
+    let id = Connection.into_stage().proxy().id();
+    match sessions.add(id, "missing".into()).unwrap().await.unwrap() {
+        Err(MonitorError::NotFound(missing)) => assert_eq!(id, missing),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn linked_actors_stop_together() {
+    let (first, first_handle) = Connection.spawn_with_handle();
+    let (second, second_handle) = Connection.spawn_with_handle();
+    first.link(second.id()).unwrap().await.unwrap().unwrap();
+
+    second.crash().unwrap();
+    assert!(matches!(second_handle.await, ExitReason::Panicked(_)));
+    assert_eq!(ExitReason::Stopped, first_handle.await);
+}
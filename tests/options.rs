@@ -0,0 +1,92 @@
+//! Tests verifying the options that can be specified with `#[thespian(...)]` when
+//! deriving `Actor`, and on the methods in a `#[thespian::actor]` impl block.
+
+#![allow(unused_imports, dead_code)]
+
+use thespian::*;
+
+mod counter {
+    use thespian::Actor;
+
+    // The handlers are private, so they can only be invoked through the proxy.
+    #[derive(Debug, Default, Actor)]
+    #[thespian(proxy = "Accumulator", proxy_vis = "pub(super)", mailbox_capacity = 2)]
+    pub struct Counter {
+        value: usize,
+    }
+
+    #[thespian::actor]
+    impl Counter {
+        fn add(&mut self, value: usize) {
+            self.value += self.checked(value);
+        }
+
+        /// Sent as a message even though it returns a value.
+        #[thespian(message)]
+        fn add_returning(&mut self, value: usize) -> usize {
+            self.value += value;
+            self.value
+        }
+
+        /// Sent as a request even though it doesn't return a value.
+        #[thespian(request)]
+        fn reset(&mut self) {
+            self.value = 0;
+        }
+
+        fn value(&self) -> usize {
+            self.value
+        }
+
+        /// Helper that doesn't get a proxy method.
+        #[thespian(skip)]
+        fn checked(&self, value: usize) -> usize {
+            self.value.checked_add(value).map(|_| value).unwrap_or(0)
+        }
+    }
+}
+
+use counter::*;
+
+#[test]
+fn renamed_proxy() {
+    let stage = Counter::default().into_stage();
+    let counter: Accumulator = stage.proxy();
+    assert!(format!("{:?}", counter).starts_with("Accumulator"));
+}
+
+#[test]
+fn restricted_visibility() {
+    // The proxy methods are `pub(super)`, so they can be called from outside the module
+    // even though the handlers are private.
+    let stage = Counter::default().into_stage();
+    stage.proxy().add(1).unwrap();
+}
+
+#[test]
+fn mailbox_capacity() {
+    let stage = Counter::default().into_stage();
+    let counter: Accumulator = stage.proxy();
+
+    counter.add(1).unwrap();
+    counter.add(1).unwrap();
+
+    let error = counter.add(1).unwrap_err();
+    assert!(matches!(error.cause(), MessageErrorCause::MailboxFull));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn message_kinds() {
+    let counter = Counter::default().spawn();
+
+    // The mailbox only has space for 2 messages, so wait for space before sending the
+    // request.
+    counter.add(1).unwrap();
+    let () = counter.add_returning(2).unwrap();
+    let value = counter.value_wait().await.unwrap();
+    assert_eq!(3, value.await.unwrap());
+
+    counter.reset().unwrap().await.unwrap();
+    assert_eq!(0, counter.value().unwrap().await.unwrap());
+}
@@ -0,0 +1,47 @@
+//! Tests verifying that a panic in a message handler is reported to requesters
+//! instead of cascading to every task communicating with the actor.
+
+#![allow(unused_imports)]
+
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Fragile {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Fragile {
+    pub fn explode(&mut self) -> usize {
+        panic!("Fragile actor exploded");
+    }
+
+    pub fn value(&self) -> usize {
+        self.value
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn panic_reported_to_requesters() {
+    let (builder, remote) = StageBuilder::new();
+    let actor = builder.spawn(Fragile::default());
+
+    // Queue both requests before awaiting either of them, so that the second request is
+    // still in the mailbox when the actor panics.
+    let exploded = actor.explode().unwrap();
+    let value = actor.value().unwrap();
+
+    match exploded.await {
+        Err(RequestError::Panicked(message)) => assert_eq!("Fragile actor exploded", message),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    match value.await {
+        Err(RequestError::ActorStopped) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    assert_eq!(ActorState::Failed, remote.state());
+    assert!(actor.value().is_err());
+}
@@ -0,0 +1,82 @@
+//! Tests verifying that messages can be sent through a `Recipient` without knowing the
+//! concrete type of the actor handling them.
+
+#![allow(unused_imports)]
+
+use futures::{future::BoxFuture, prelude::*};
+use thespian::*;
+
+#[derive(Debug, Clone)]
+pub struct LogLine(String);
+
+#[derive(Debug, Default, Actor)]
+pub struct Console {
+    lines: Vec<String>,
+}
+
+#[thespian::actor]
+impl Console {
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.clone()
+    }
+}
+
+impl Handler<LogLine> for Console {
+    type Output = ();
+
+    fn handle(&mut self, line: LogLine, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+        self.lines.push(line.0);
+        future::ready(()).boxed()
+    }
+}
+
+#[derive(Debug, Default, Actor)]
+pub struct LineCounter {
+    count: usize,
+}
+
+#[thespian::actor]
+impl LineCounter {}
+
+impl Handler<LogLine> for LineCounter {
+    type Output = usize;
+
+    fn handle(&mut self, _: LogLine, _: &mut Context<Self>) -> BoxFuture<'_, usize> {
+        self.count += 1;
+        future::ready(self.count).boxed()
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn send_to_any_handler() {
+    let console = Console::default().spawn();
+    let counter = LineCounter::default().spawn();
+
+    // The producer only knows that each recipient handles `LogLine`.
+    let recipients: Vec<Recipient<LogLine>> = vec![console.recipient(), counter.recipient()];
+    for recipient in &recipients {
+        recipient.send_message(LogLine("hello".into())).unwrap();
+    }
+
+    let lines = console.lines().unwrap().await.unwrap();
+    assert_eq!(vec!["hello".to_string()], lines);
+
+    let requests: RequestRecipient<LogLine, usize> = counter.request_recipient();
+    let count = requests.send_request(LogLine("world".into())).unwrap();
+    assert_eq!(2, count.await.unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn recipient_keeps_actor_alive() {
+    let (counter, handle) = LineCounter::default().spawn_with_handle();
+    let recipient = counter.request_recipient::<LogLine>();
+    drop(counter);
+
+    let count = recipient.send_request(LogLine("hello".into())).unwrap();
+    assert_eq!(1, count.await.unwrap());
+
+    drop(recipient);
+    assert_eq!(ExitReason::ProxiesDropped, handle.await);
+}
@@ -0,0 +1,106 @@
+//! Tests verifying that an actor can await a request without blocking its mailbox,
+//! allowing two actors to send requests to each other without deadlocking.
+//!
+//! This is the request-based version of the cycle in `deadlock.rs`: `Foo` sends a
+//! request to `Bar` while handling a message, and `Bar` sends a request back to `Foo`
+//! while handling that request.
+
+#![allow(unused_imports, clippy::disallowed_names)]
+
+use futures::prelude::*;
+use std::time::Duration;
+use thespian::*;
+
+#[derive(Debug, Actor)]
+pub struct Foo {
+    value: usize,
+    bar: BarProxy,
+}
+
+#[thespian::actor]
+impl Foo {
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    pub fn add(&mut self, value: usize) {
+        self.value += value;
+    }
+
+    pub fn double_with_bar(&mut self, ctx: &mut Context<Self>) -> ResponseFuture<usize> {
+        let doubled = self.bar.double_foo().unwrap();
+        ctx.await_then(doubled, |foo, _, doubled| {
+            foo.value = doubled.unwrap();
+            foo.value
+        })
+    }
+
+    pub fn wait_forever(&mut self, ctx: &mut Context<Self>) -> ResponseFuture<()> {
+        let response = ctx.await_then(future::pending::<()>(), |_, _, _| {});
+        ctx.stop();
+        response
+    }
+}
+
+#[derive(Debug, Actor)]
+pub struct Bar {
+    foo: FooProxy,
+}
+
+#[thespian::actor]
+impl Bar {
+    pub async fn double_foo(&mut self) -> usize {
+        self.foo.value().unwrap().await.unwrap() * 2
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn spawn_pair() -> FooProxy {
+    let (foo_stage, foo_remote) = StageBuilder::new();
+    let (bar_stage, bar_remote) = StageBuilder::new();
+    foo_stage.spawn(Foo {
+        value: 1,
+        bar: bar_remote.proxy(),
+    });
+    bar_stage.spawn(Bar {
+        foo: foo_remote.proxy(),
+    });
+    foo_remote.proxy()
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn request_cycle() {
+    let foo = spawn_pair();
+
+    // The `add` message is handled while `Foo` is waiting on `Bar`, so `Bar` sees the
+    // updated value.
+    let doubled = foo.double_with_bar().unwrap();
+    foo.add(1).unwrap();
+
+    let doubled =
+        tokio::time::timeout(
+            Duration::from_millis(500),
+            async move { doubled.await.unwrap() },
+        )
+        .await
+        .expect("Actors deadlocked");
+    assert_eq!(4, doubled);
+    assert_eq!(4, foo.value().unwrap().await.unwrap());
+
+    // The blocking method also waits for the final response.
+    let blocking = foo.clone();
+    let doubled = tokio::task::spawn_blocking(move || blocking.double_with_bar_blocking());
+    assert_eq!(8, doubled.await.unwrap().unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stopped_before_completion() {
+    let foo = spawn_pair();
+
+    match foo.wait_forever().unwrap().await {
+        Err(RequestError::ActorStopped) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
@@ -0,0 +1,93 @@
+//! Tests verifying that actors can be registered by name and looked up by other code.
+//!
+//! The registry is global, so each test uses a unique name in order to avoid
+//! interfering with the other tests when run in parallel.
+
+#![allow(unused_imports)]
+
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Counter {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Counter {
+    pub fn add(&mut self, value: usize) {
+        self.value += value;
+    }
+
+    pub fn value(&self) -> usize {
+        self.value
+    }
+}
+
+#[derive(Debug, Default, Actor)]
+pub struct Other;
+
+#[thespian::actor]
+impl Other {}
+
+#[test]
+fn lookup_by_name_and_type() {
+    let (builder, _) = StageBuilder::new();
+    let _stage = builder
+        .name("lookup_by_name_and_type")
+        .unwrap()
+        .finish(Counter::default());
+
+    assert!(lookup::<Counter>("lookup_by_name_and_type").is_some());
+    assert!(lookup::<Other>("lookup_by_name_and_type").is_none());
+    assert!(lookup::<Counter>("missing").is_none());
+}
+
+#[test]
+fn name_taken() {
+    let (builder, _) = StageBuilder::new();
+    let _stage = builder
+        .name("name_taken")
+        .unwrap()
+        .finish(Counter::default());
+
+    let (builder, _) = StageBuilder::<Other>::new();
+    let error = builder.name("name_taken").err().unwrap();
+    assert_eq!(RegistryError::NameTaken("name_taken".into()), error);
+}
+
+#[test]
+fn dropped_stage_frees_name() {
+    let (builder, _) = StageBuilder::new();
+    let stage = builder
+        .name("dropped_stage_frees_name")
+        .unwrap()
+        .finish(Counter::default());
+    drop(stage);
+
+    assert!(lookup::<Counter>("dropped_stage_frees_name").is_none());
+    let (builder, _) = StageBuilder::<Counter>::new();
+    assert!(builder.name("dropped_stage_frees_name").is_ok());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn deregister_on_stop() {
+    let (builder, remote) = StageBuilder::new();
+    let (counter, handle) = builder
+        .name("deregister_on_stop")
+        .unwrap()
+        .spawn_with_handle(Counter::default());
+    counter.add(1).unwrap();
+
+    let found = lookup::<Counter>("deregister_on_stop").unwrap();
+    assert_eq!(1, found.value().unwrap().await.unwrap());
+
+    remote.stop().unwrap();
+    handle.await;
+    assert!(lookup::<Counter>("deregister_on_stop").is_none());
+
+    // The name can be reused once the original actor has stopped.
+    Counter::default()
+        .spawn_named("deregister_on_stop")
+        .unwrap();
+}
@@ -0,0 +1,77 @@
+//! Tests verifying that streams can be attached to an actor, with each item in the
+//! stream being delivered to the actor as a message.
+
+#![allow(unused_imports)]
+
+use futures::{future::BoxFuture, prelude::*, stream};
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Reader {
+    items: Vec<usize>,
+    finished: usize,
+}
+
+#[thespian::actor]
+impl Reader {
+    pub fn count(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn attach(&mut self, items: Vec<usize>, ctx: &mut Context<Self>) {
+        ctx.add_stream(stream::iter(items));
+    }
+}
+
+impl Handler<usize> for Reader {
+    type Output = ();
+
+    fn handle(&mut self, item: usize, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+        self.items.push(item);
+        future::ready(()).boxed()
+    }
+}
+
+impl StreamHandler<usize> for Reader {
+    fn finished(&mut self, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+        self.finished += 1;
+        future::ready(()).boxed()
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stream_keeps_actor_alive() {
+    let mut stage = Reader::default().into_stage();
+    stage.add_stream(stream::iter(vec![1, 2, 3]));
+
+    // No proxies are held outside of the stage, but the actor keeps running until the
+    // stream has ended.
+    let proxy = stage.proxy();
+    proxy.attach(vec![4, 5]).unwrap();
+    drop(proxy);
+
+    let reader = stage.run_to_completion().await.unwrap();
+    let mut items = reader.items.clone();
+    items.sort();
+    assert_eq!(vec![1, 2, 3, 4, 5], items);
+    assert_eq!(2, reader.finished);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn interleaved_with_mailbox() {
+    let mut stage = Reader::default().into_stage();
+    stage.add_stream(stream::iter(0..1000));
+
+    // The request is queued before the actor starts, so if items from the stream were
+    // prioritized over the mailbox the actor would have handled the entire stream first.
+    let proxy = stage.proxy();
+    let count = proxy.count().unwrap();
+    let handle = stage.join_handle();
+    tokio::spawn(stage.run());
+
+    assert!(count.await.unwrap() <= 1);
+    drop(proxy);
+    assert_eq!(ExitReason::ProxiesDropped, handle.await);
+}
@@ -0,0 +1,113 @@
+//! Tests verifying that supervised actors are restarted according to the
+//! supervisor's restart strategy, and that proxies keep working across restarts.
+
+#![allow(unused_imports)]
+
+use futures::{channel::mpsc, prelude::*};
+use std::time::Duration;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Counter {
+    value: usize,
+}
+
+#[thespian::actor]
+impl Counter {
+    pub fn add(&mut self, value: usize) -> usize {
+        self.value += value;
+        self.value
+    }
+
+    pub fn explode(&mut self) {
+        panic!("Counter exploded");
+    }
+}
+
+/// Returns a factory for `Counter` that notifies the returned receiver each time the
+/// actor is created.
+#[cfg(feature = "tokio")]
+fn counter_factory() -> (impl FnMut() -> Counter, mpsc::UnboundedReceiver<()>) {
+    let (sender, receiver) = mpsc::unbounded();
+    let factory = move || {
+        sender.unbounded_send(()).unwrap();
+        Counter::default()
+    };
+
+    (factory, receiver)
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn one_for_one() {
+    let mut supervisor = Supervisor::new(RestartStrategy::OneForOne);
+    let (factory, mut first_started) = counter_factory();
+    let first = supervisor.add_child(factory);
+    let (factory, mut second_started) = counter_factory();
+    let second = supervisor.add_child(factory);
+    supervisor.spawn();
+
+    first_started.next().await.unwrap();
+    second_started.next().await.unwrap();
+
+    assert_eq!(5, first.add(5).unwrap().await.unwrap());
+    assert_eq!(7, second.add(7).unwrap().await.unwrap());
+
+    // Only the failed actor is restarted, and its existing proxy is still usable.
+    first.explode().unwrap();
+    first_started.next().await.unwrap();
+    assert_eq!(1, first.add(1).unwrap().await.unwrap());
+    assert_eq!(8, second.add(1).unwrap().await.unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn one_for_all() {
+    let mut supervisor = Supervisor::new(RestartStrategy::OneForAll);
+    let (factory, mut first_started) = counter_factory();
+    let first = supervisor.add_child(factory);
+    let (factory, mut second_started) = counter_factory();
+    let second = supervisor.add_child(factory);
+    supervisor.spawn();
+
+    first_started.next().await.unwrap();
+    second_started.next().await.unwrap();
+
+    assert_eq!(5, first.add(5).unwrap().await.unwrap());
+    assert_eq!(7, second.add(7).unwrap().await.unwrap());
+
+    // Both actors are restarted when the second one fails.
+    second.explode().unwrap();
+    first_started.next().await.unwrap();
+    second_started.next().await.unwrap();
+    assert_eq!(1, first.add(1).unwrap().await.unwrap());
+    assert_eq!(1, second.add(1).unwrap().await.unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn restart_intensity() {
+    let mut supervisor =
+        Supervisor::new(RestartStrategy::OneForOne).max_restarts(1, Duration::from_secs(60));
+    let (builder, remote) = StageBuilder::new();
+    let (factory, mut started) = counter_factory();
+    let counter = supervisor.add_child_with(builder, factory);
+    supervisor.spawn();
+    started.next().await.unwrap();
+
+    // The first failure is within the restart limit.
+    counter.explode().unwrap();
+    started.next().await.unwrap();
+    assert_eq!(1, counter.add(1).unwrap().await.unwrap());
+
+    // The second failure exceeds the limit, so the actor is stopped for good and any
+    // queued messages are discarded.
+    counter.explode().unwrap();
+    match counter.add(1).unwrap().await {
+        Err(RequestError::ActorStopped) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    assert_eq!(ActorState::Failed, remote.state());
+    assert!(counter.add(1).is_err());
+}
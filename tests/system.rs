@@ -0,0 +1,78 @@
+//! Tests verifying that an `ActorSystem` tracks the actors spawned through it and
+//! shuts them down together.
+
+#![allow(unused_imports)]
+
+use futures::{channel::mpsc, future::BoxFuture, prelude::*};
+use thespian::*;
+
+#[derive(Debug, Actor)]
+#[thespian(proxy_only)]
+pub struct Recorder {
+    id: usize,
+    stopped: mpsc::UnboundedSender<usize>,
+}
+
+impl Actor for Recorder {
+    type Proxy = RecorderProxy;
+
+    fn stopped(&mut self) -> BoxFuture<'_, ()> {
+        self.stopped.unbounded_send(self.id).unwrap();
+        future::ready(()).boxed()
+    }
+}
+
+#[thespian::actor]
+impl Recorder {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn shutdown_in_reverse_order() {
+    let system = ActorSystem::new();
+    let (stopped, receiver) = mpsc::unbounded();
+
+    // Hold onto the proxies so that the actors keep running until they're stopped.
+    let mut recorders = Vec::new();
+    for id in 0..3 {
+        recorders.push(system.spawn(Recorder {
+            id,
+            stopped: stopped.clone(),
+        }));
+    }
+    drop(stopped);
+
+    assert_eq!(3, system.len());
+    let actors = system.actors();
+    assert!(actors
+        .iter()
+        .all(|info| info.type_name().ends_with("Recorder")));
+
+    system.shutdown().await;
+    assert!(system.is_empty());
+    assert!(actors
+        .iter()
+        .all(|info| info.state() == ActorState::Stopped));
+
+    let order = receiver.collect::<Vec<_>>().await;
+    assert_eq!(vec![2, 1, 0], order);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stopped_actors_not_tracked() {
+    let system = ActorSystem::new();
+    let (stopped, _receiver) = mpsc::unbounded();
+
+    let recorder = system.spawn(Recorder { id: 0, stopped });
+    assert_eq!(0, recorder.id().unwrap().await.unwrap());
+
+    // Dropping the last proxy stops the actor without needing to shut down the system.
+    let handle = system.actors()[0].stopped();
+    drop(recorder);
+    assert_eq!(ExitReason::ProxiesDropped, handle.await);
+    assert!(system.is_empty());
+}
@@ -0,0 +1,34 @@
+//! Tests verifying that requests time out if the actor doesn't respond in time.
+
+#![allow(unused_imports)]
+
+use std::time::Duration;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Sleepy;
+
+#[thespian::actor]
+impl Sleepy {
+    pub async fn sleep(&mut self, duration: Duration) -> Duration {
+        #[cfg(feature = "tokio")]
+        tokio::time::delay_for(duration).await;
+        duration
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn request_timeout() {
+    let actor = Sleepy.spawn();
+
+    let response = actor.sleep(Duration::from_millis(200)).unwrap();
+    let result = timeout(Duration::from_millis(10), response).await;
+    assert!(matches!(result, Err(RequestError::TimedOut)));
+
+    // The actor is still able to respond to requests once it has finished handling the
+    // request that timed out.
+    let response = actor.sleep(Duration::from_millis(1)).unwrap();
+    let result = timeout(Duration::from_secs(10), response).await;
+    assert_eq!(Duration::from_millis(1), result.unwrap());
+}
@@ -0,0 +1,149 @@
+//! Tests verifying that actors can schedule delayed and repeating messages without the
+//! pending timers keeping the actor alive.
+
+#![allow(unused_imports)]
+
+use futures::{channel::mpsc, future::BoxFuture, prelude::*};
+use std::time::Duration;
+use thespian::*;
+
+#[derive(Debug, Default, Actor)]
+pub struct Heartbeat {
+    beats: usize,
+
+    // Notified with the new count after each beat, so that tests can wait for a beat to
+    // be handled.
+    notify: Option<mpsc::UnboundedSender<usize>>,
+
+    #[cfg(feature = "tokio")]
+    timer: Option<TimerHandle>,
+}
+
+impl Heartbeat {
+    #[cfg(feature = "tokio")]
+    fn notifying() -> (Self, mpsc::UnboundedReceiver<usize>) {
+        let (notify, beats) = mpsc::unbounded();
+        let heartbeat = Heartbeat {
+            notify: Some(notify),
+            ..Default::default()
+        };
+        (heartbeat, beats)
+    }
+
+    fn beat(&mut self) {
+        self.beats += 1;
+        if let Some(notify) = &self.notify {
+            let _ = notify.unbounded_send(self.beats);
+        }
+    }
+}
+
+#[thespian::actor]
+impl Heartbeat {
+    pub fn beats(&self) -> usize {
+        self.beats
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[thespian::actor]
+impl Heartbeat {
+    pub fn start(&mut self, period: Duration, ctx: &mut Context<Self>) {
+        let timer = ctx.run_interval(period, |heartbeat, _| heartbeat.beat());
+        self.timer = Some(timer);
+    }
+
+    pub fn stop_beating(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.cancel();
+        }
+    }
+}
+
+pub struct Beat;
+
+impl Handler<Beat> for Heartbeat {
+    type Output = ();
+
+    fn handle(&mut self, _: Beat, _: &mut Context<Self>) -> BoxFuture<'_, ()> {
+        self.beat();
+        future::ready(()).boxed()
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn interval_until_cancelled() {
+    tokio::time::pause();
+    let (heartbeat, mut beats) = Heartbeat::notifying();
+    let heartbeat = heartbeat.spawn();
+
+    // Wait for the actor to start the timer before moving the clock forward.
+    heartbeat.start(Duration::from_millis(10)).unwrap();
+    assert_eq!(0, heartbeat.beats().unwrap().await.unwrap());
+
+    for expected in 1..=3 {
+        tokio::time::advance(Duration::from_millis(10)).await;
+        assert_eq!(Some(expected), beats.next().await);
+    }
+
+    heartbeat.stop_beating().unwrap();
+    assert_eq!(3, heartbeat.beats().unwrap().await.unwrap());
+
+    // No more beats are delivered once the timer has been cancelled.
+    tokio::time::advance(Duration::from_millis(30)).await;
+    assert_eq!(3, heartbeat.beats().unwrap().await.unwrap());
+    assert!(beats.next().now_or_never().is_none());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn send_after() {
+    tokio::time::pause();
+    let (heartbeat, mut beats) = Heartbeat::notifying();
+    let heartbeat = heartbeat.spawn();
+
+    heartbeat.send_after(Duration::from_millis(10), Beat);
+    let cancelled = heartbeat.send_after(Duration::from_millis(10), Beat);
+    cancelled.cancel();
+    assert!(cancelled.is_cancelled());
+
+    assert_eq!(0, heartbeat.beats().unwrap().await.unwrap());
+    tokio::time::advance(Duration::from_millis(10)).await;
+    assert_eq!(Some(1), beats.next().await);
+
+    // The cancelled timer never delivers its message.
+    tokio::time::advance(Duration::from_millis(30)).await;
+    assert_eq!(1, heartbeat.beats().unwrap().await.unwrap());
+    assert!(beats.next().now_or_never().is_none());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn timers_dont_keep_actor_alive() {
+    let (heartbeat, handle) = Heartbeat::default().spawn_with_handle();
+
+    heartbeat.start(Duration::from_millis(10)).unwrap();
+    heartbeat.send_after(Duration::from_millis(10), Beat);
+    drop(heartbeat);
+
+    assert_eq!(ExitReason::ProxiesDropped, handle.await);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn fires_after_actor_stopped() {
+    let (reasons, mut letters) = mpsc::unbounded();
+    let (builder, _) = StageBuilder::new();
+    let stage = builder
+        .dead_letters(move |letter| {
+            let _ = reasons.unbounded_send(letter.reason());
+        })
+        .finish(Heartbeat::default());
+
+    // The actor stops before the timer fires, so the message becomes a dead letter.
+    stage.proxy().send_after(Duration::from_millis(10), Beat);
+    drop(stage);
+
+    assert_eq!(Some(DeadLetterReason::ActorStopped), letters.next().await);
+}
@@ -1,152 +1,974 @@
 use proc_macro2::{Literal, TokenStream};
 use quote::*;
-use syn::{punctuated::Punctuated, *};
+use syn::{parse::Parser, punctuated::Punctuated, *};
 
-#[proc_macro_derive(Actor)]
+#[proc_macro_derive(Actor, attributes(thespian))]
 pub fn derive_actor(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
-    let vis = input.vis;
+    let options = match ActorOptions::from_attrs(&input.attrs) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    if let Err(err) = reject_lifetimes(&input.generics) {
+        return err.to_compile_error().into();
+    }
+
     let actor_ident = input.ident;
-    let proxy_ident = format_ident!("{}Proxy", actor_ident);
+    let vis = input.vis;
+    let proxy_ident = options
+        .proxy
+        .unwrap_or_else(|| format_ident!("{}Proxy", actor_ident));
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let actor_ty = quote! { #actor_ident #ty_generics };
 
+    // Only generate the `Actor` impl if the user isn't going to provide their own,
+    // e.g. in order to override the lifecycle hooks.
+    let actor_impl = if options.proxy_only {
+        quote! {}
+    } else {
+        let mailbox_capacity = options.mailbox_capacity.map(|capacity| {
+            quote! { const MAILBOX_CAPACITY: usize = #capacity; }
+        });
+
+        // Handling messages concurrently requires the actor to be `Sync`.
+        let (sync_bound, shared_access) = if options.concurrent_reads.is_some() {
+            let shared_access = quote! {
+                fn shared_access(&self) -> Option<thespian::SharedAccess<'_, Self>> {
+                    Some(thespian::SharedAccess::new(self))
+                }
+            };
+            (quote! { + Sync }, shared_access)
+        } else {
+            (quote! {}, quote! {})
+        };
+
+        let where_clause = with_predicates(
+            where_clause,
+            quote! { #actor_ty: Send #sync_bound + 'static },
+        );
+        quote! {
+            impl #impl_generics thespian::Actor for #actor_ty #where_clause {
+                type Proxy = #proxy_ident #ty_generics;
+                #mailbox_capacity
+                #shared_access
+            }
+        }
+    };
+
+    // NOTE: The generated proxy type only holds a `ProxyFor`, so we implement `Clone`,
+    // `Debug`, `PartialEq` and `Hash` manually rather than deriving them. Deriving
+    // would require the actor's type parameters to implement those traits as well. We
+    // also need to specify the actor's proxy type in the bounds, otherwise the compiler
+    // can't tell that `<A as Actor>::Proxy` is the generated proxy type.
+    let proxy_where_clause = with_predicates(
+        where_clause,
+        quote! { #actor_ty: thespian::Actor<Proxy = #proxy_ident #ty_generics> },
+    );
+    let proxy_name = proxy_ident.to_string();
+
+    // NOTE: `#[thespian::actor]` can't see the options given here, so we generate a macro
+    // that passes them on. The actor macro forwards the impl block to this macro, which
+    // invokes the actor macro again with the options filled in. The macro is found by
+    // name, so the impl block has to come after the actor type, either in the same
+    // module or in a child module.
+    let forward_macro = forward_macro_ident(&actor_ident);
+    let proxy_vis = options.proxy_vis.map(|vis| {
+        let vis = vis.to_token_stream().to_string();
+        quote! { , proxy_vis = #vis }
+    });
     let generated = quote! {
-        impl thespian::Actor for #actor_ident {
-            type Proxy = #proxy_ident;
+        #actor_impl
+
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #forward_macro {
+            ($($item:tt)*) => {
+                #[thespian::actor(derived(proxy = #proxy_name #proxy_vis))]
+                $($item)*
+            };
+        }
+
+        #vis struct #proxy_ident #generics #proxy_where_clause {
+            inner: thespian::ProxyFor<#actor_ty>,
+        }
+
+        impl #impl_generics Clone for #proxy_ident #ty_generics #proxy_where_clause {
+            fn clone(&self) -> Self {
+                Self { inner: self.inner.clone() }
+            }
+        }
+
+        impl #impl_generics std::fmt::Debug for #proxy_ident #ty_generics #proxy_where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(#proxy_name).field("inner", &self.inner).finish()
+            }
+        }
+
+        impl #impl_generics PartialEq for #proxy_ident #ty_generics #proxy_where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                self.inner == other.inner
+            }
         }
 
-        #[derive(Debug, Clone)]
-        #vis struct #proxy_ident {
-            inner: thespian::ProxyFor<#actor_ident>,
+        impl #impl_generics Eq for #proxy_ident #ty_generics #proxy_where_clause {}
+
+        impl #impl_generics std::hash::Hash for #proxy_ident #ty_generics #proxy_where_clause {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.inner.hash(state);
+            }
         }
 
-        impl thespian::ActorProxy for #proxy_ident {
-            type Actor = #actor_ident;
+        impl #impl_generics thespian::ActorProxy for #proxy_ident #ty_generics #proxy_where_clause {
+            type Actor = #actor_ty;
 
-            fn new(inner: thespian::ProxyFor<#actor_ident>) -> Self {
+            fn new(inner: thespian::ProxyFor<#actor_ty>) -> Self {
                 Self { inner }
             }
+
+            fn inner(&self) -> &thespian::ProxyFor<#actor_ty> {
+                &self.inner
+            }
         }
     };
 
     generated.into()
 }
 
+/// Options specified on the actor type via the `#[thespian(...)]` attribute.
+#[derive(Default)]
+struct ActorOptions {
+    /// Only generate the proxy type, leaving the `Actor` impl to the user.
+    proxy_only: bool,
+
+    /// The name of the generated proxy type, overriding the default `{Actor}Proxy`.
+    proxy: Option<Ident>,
+
+    /// The visibility of the generated proxy methods, overriding the visibility of the
+    /// corresponding methods on the actor.
+    proxy_vis: Option<Visibility>,
+
+    /// The default capacity of the actor's mailbox.
+    mailbox_capacity: Option<LitInt>,
+
+    /// Handle read-only messages concurrently.
+    concurrent_reads: Option<Path>,
+}
+
+impl ActorOptions {
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for nested in thespian_options(attrs)? {
+            match &nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("proxy_only") => {
+                    options.proxy_only = true;
+                }
+
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("concurrent_reads") => {
+                    options.concurrent_reads = Some(path.clone());
+                }
+
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("proxy") => {
+                    options.proxy = Some(lit_str(&meta.lit)?.parse()?);
+                }
+
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("proxy_vis") => {
+                    options.proxy_vis = Some(lit_str(&meta.lit)?.parse()?);
+                }
+
+                NestedMeta::Meta(Meta::NameValue(meta))
+                    if meta.path.is_ident("mailbox_capacity") =>
+                {
+                    let capacity = match &meta.lit {
+                        Lit::Int(capacity) => capacity,
+                        lit => return Err(Error::new_spanned(lit, "Expected an integer")),
+                    };
+
+                    if capacity.base10_parse::<usize>()? == 0 {
+                        return Err(Error::new_spanned(
+                            capacity,
+                            "Mailbox capacity must be greater than 0",
+                        ));
+                    }
+
+                    options.mailbox_capacity = Some(capacity.clone());
+                }
+
+                _ => return Err(Error::new_spanned(nested, "Unknown thespian option")),
+            }
+        }
+
+        if options.proxy_only {
+            if let Some(capacity) = &options.mailbox_capacity {
+                return Err(Error::new_spanned(
+                    capacity,
+                    "`mailbox_capacity` can't be used with `proxy_only`, set `Actor::MAILBOX_CAPACITY` instead",
+                ));
+            }
+
+            if let Some(path) = &options.concurrent_reads {
+                return Err(Error::new_spanned(
+                    path,
+                    "`concurrent_reads` can't be used with `proxy_only`, override `Actor::shared_access` instead",
+                ));
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// The actor's options passed on to `#[thespian::actor]` by the macro generated when
+/// deriving `Actor`, as `#[thespian::actor(derived(...))]`.
+///
+/// These aren't meant to be specified by hand, the options are given when deriving
+/// `Actor` instead.
+#[derive(Default)]
+struct ImplOptions {
+    /// Whether the options have been passed on from the derive yet.
+    derived: bool,
+
+    /// The name of the proxy type.
+    proxy: Option<Ident>,
+
+    /// The visibility of the generated proxy methods, overriding the visibility of the
+    /// corresponding methods on the actor.
+    proxy_vis: Option<Visibility>,
+}
+
+impl ImplOptions {
+    fn from_args(args: &[NestedMeta]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for nested in args {
+            let list = match nested {
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("derived") => list,
+                _ => return Err(Error::new_spanned(nested, "Unknown thespian option")),
+            };
+            options.derived = true;
+
+            for nested in &list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("proxy") => {
+                        options.proxy = Some(lit_str(&meta.lit)?.parse()?);
+                    }
+
+                    NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("proxy_vis") => {
+                        options.proxy_vis = Some(lit_str(&meta.lit)?.parse()?);
+                    }
+
+                    _ => return Err(Error::new_spanned(nested, "Unknown thespian option")),
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// The name of the macro generated when deriving `Actor`, which forwards an impl block
+/// for the actor back to `#[thespian::actor]` along with the actor's options.
+fn forward_macro_ident(actor_ident: &Ident) -> Ident {
+    format_ident!(
+        "__thespian_actor_{}",
+        actor_ident,
+        span = actor_ident.span()
+    )
+}
+
+/// Options specified on a method within an actor's impl block via the
+/// `#[thespian(...)]` attribute.
+#[derive(Default)]
+struct MethodOptions {
+    /// Don't generate a message for the method, e.g. for private helper methods.
+    skip: bool,
+
+    /// Whether the proxy waits for a response, overriding the default of only waiting
+    /// if the method returns a value.
+    request: Option<bool>,
+}
+
+impl MethodOptions {
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for nested in thespian_options(attrs)? {
+            match &nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {
+                    options.skip = true;
+                }
+
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("message") => {
+                    options.request = Some(false);
+                }
+
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("request") => {
+                    options.request = Some(true);
+                }
+
+                _ => return Err(Error::new_spanned(nested, "Unknown thespian option")),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Collects the options from all `#[thespian(...)]` attributes.
+fn thespian_options(attrs: &[Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut options = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("thespian")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => options.extend(list.nested),
+            meta => return Err(Error::new_spanned(meta, "Expected `#[thespian(...)]`")),
+        }
+    }
+
+    Ok(options)
+}
+
+fn lit_str(lit: &Lit) -> syn::Result<&LitStr> {
+    match lit {
+        Lit::Str(lit) => Ok(lit),
+        _ => Err(Error::new_spanned(lit, "Expected a string literal")),
+    }
+}
+
 #[proc_macro_attribute]
 pub fn actor(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     tokens: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let mut result = TokenStream::from(tokens.clone());
+    let args = parse_macro_input!(args as AttributeArgs);
+    let options = match ImplOptions::from_args(&args) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     // Parse the input as an impl block, rejecting any other item types.
-    let input = parse_macro_input!(tokens as ItemImpl);
+    let mut input = parse_macro_input!(tokens as ItemImpl);
+
+    // Hand the impl block to the macro generated when deriving `Actor`, which invokes this
+    // macro again with the actor's options.
+    if !options.derived {
+        let actor_ident = match &*input.self_ty {
+            Type::Path(path) => &path.path.segments.last().expect("Empty type path").ident,
+            ty => return Error::new_spanned(ty, "Unsupported type expression, only type paths are supported, e.g. `Foo` or `foo::bar::Baz`")
+                .to_compile_error()
+                .into(),
+        };
+        let forward_macro = forward_macro_ident(actor_ident);
+        return quote! { #forward_macro! { #input } }.into();
+    }
 
     // Gather all valid method definitions in the impl block, specifically ones with a
-    // receiver since associated functions can't be used as messages.
-    let methods = input
-        .items
-        .iter()
-        .filter_map(|item| match item {
-            ImplItem::Method(item) => Some(item),
-            _ => None,
-        })
-        .filter(|method| method.sig.receiver().is_some())
-        .collect::<Vec<_>>();
+    // receiver since associated functions can't be used as messages. The
+    // `#[thespian(...)]` attributes are removed from the methods, since they're not
+    // valid in the output.
+    let mut methods = Vec::new();
+    for item in &mut input.items {
+        let method = match item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+
+        let method_options = match MethodOptions::from_attrs(&method.attrs) {
+            Ok(options) => options,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        method.attrs.retain(|attr| !attr.path.is_ident("thespian"));
+
+        if input.trait_.is_some() && (method_options.skip || method_options.request.is_some()) {
+            return Error::new_spanned(
+                &method.sig,
+                "Method options are not supported in interface impls",
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        if method.sig.receiver().is_some() && !method_options.skip {
+            methods.push((method.clone(), method_options));
+        }
+    }
 
-    let self_ty = input.self_ty;
-    let mangled_self_ty = match mangled_type_name(&self_ty) {
+    let mut result = input.to_token_stream();
+
+    let self_ty = &input.self_ty;
+    let mangled_self_ty = match mangled_type_name(self_ty) {
         Ok(name) => name,
         Err(err) => return err.to_compile_error().into(),
     };
-    let proxy_ty = format_ident!("{}Proxy", mangled_self_ty);
+    let proxy_ident = options
+        .proxy
+        .expect("Proxy name not passed on from `#[derive(Actor)]`");
 
-    // Collect the generated items for each message handler defined in the impl block.
-    let generated = methods
-        .iter()
-        .map(|method| {
-            let vis = &method.vis;
-            let method_name = &method.sig.ident;
-            let message_ty = format_ident!("{}__{}", mangled_self_ty, method.sig.ident);
-
-            let inputs = method
-                .sig
-                .inputs
-                .iter()
-                .filter_map(|arg| match arg {
-                    FnArg::Typed(arg) => Some(arg),
-                    FnArg::Receiver(_) => None,
-                })
-                .collect::<Punctuated<_, Token![,]>>();
-
-            // Extract normalized names and the type for each input for the message.
-            let input_name = inputs
-                .iter()
-                .enumerate()
-                .map(|(index, arg)| match &*arg.pat {
-                    Pat::Ident(pat) => pat.ident.clone(),
-                    _ => format_ident!("arg{}", index),
-                })
-                .collect::<Vec<_>>();
-            let input_ty = inputs.iter().map(|arg| &arg.ty).collect::<Vec<_>>();
-            let input_index = inputs.iter().enumerate().map(|(index, _)| Literal::usize_unsuffixed(index));
+    if let Err(err) = reject_lifetimes(&input.generics) {
+        return err.to_compile_error().into();
+    }
 
-            let output_ty = match &method.sig.output {
-                ReturnType::Default => quote! { () },
-                ReturnType::Type(_, output) => output.to_token_stream(),
-            };
+    // The proxy type has the same generic parameters as the actor type, so we reuse the
+    // arguments from the actor type for the proxy type.
+    let proxy_args = match &**self_ty {
+        Type::Path(path) => path.path.segments.last().map(|segment| &segment.arguments),
+        _ => None,
+    };
+    let proxy_ty = quote! { #proxy_ident #proxy_args };
 
-            let proxy_fn_output_ty = match &method.sig.output {
-                ReturnType::Default => quote! { () },
-                ReturnType::Type(_, output) => quote! { impl std::future::Future<Output = #output> }
-            };
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let turbofish = ty_generics.as_turbofish();
+    let proxy_where_clause = with_predicates(
+        where_clause,
+        quote! { #self_ty: thespian::Actor<Proxy = #proxy_ty> },
+    );
 
-            let send_fn = match method.sig.output {
-                ReturnType::Default => quote! { send_message },
-                ReturnType::Type(..) => quote! { send_request },
-            };
+    // If the impl block implements an interface trait, the proxy implements the
+    // proxy-side trait generated by `#[thespian::interface]` instead of getting inherent
+    // methods.
+    let interface = match &input.trait_ {
+        Some((Some(bang), ..)) => {
+            return Error::new_spanned(bang, "Negative impls are not supported")
+                .to_compile_error()
+                .into()
+        }
+        Some((None, path, _)) => Some(path),
+        None => None,
+    };
+    let message_prefix = match interface {
+        Some(path) => format_ident!(
+            "{}__{}",
+            mangled_self_ty,
+            path.segments.last().unwrap().ident
+        ),
+        None => mangled_self_ty.clone(),
+    };
 
-            // If the message handler is an async fn, we need to append `.await` when we invoke
-            // the method in order to ensure we fully execute the handler.
-            let dot_await = match &method.sig.asyncness {
-                Some(_) => quote! { .await },
-                None => quote! {},
-            };
+    // Message types need to use all of the impl block's type parameters, so generic
+    // message types get an extra `PhantomData` field. We use `fn() -> T` so that the
+    // message type is `Send` regardless of the type parameters.
+    let type_params = generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect::<Vec<_>>();
+    let (phantom_field, phantom_init) = if type_params.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! { std::marker::PhantomData<fn() -> ( #( #type_params, )* )> },
+            quote! { std::marker::PhantomData },
+        )
+    };
 
-            quote! {
-                // Generate inherent impl on proxy type.
-                impl #proxy_ty {
-                    #vis fn #method_name(&mut self, #( #input_name: #input_ty, )*) -> thespian::Result<#proxy_fn_output_ty> {
-                        self.inner.#send_fn(#message_ty( #( #input_name, )* ))
-                    }
-                }
+    // Collect the generated items for each message handler defined in the impl block,
+    // keeping the proxy methods separate so that they can be put in a single trait impl.
+    let mut proxy_methods = TokenStream::new();
+    let mut messages = TokenStream::new();
+    for (method, method_options) in &methods {
+        let vis = options.proxy_vis.as_ref().unwrap_or(&method.vis);
+        let method_name = &method.sig.ident;
+        let message_ty = format_ident!("{}__{}", message_prefix, method.sig.ident);
+        let message_name = format!("{}::{}", mangled_self_ty, method.sig.ident);
+
+        let (input_name, input_ty) = message_inputs(&method.sig);
+        let handler_args = handler_args(&method.sig);
+
+        let output_ty = match &method.sig.output {
+            ReturnType::Default => quote! { () },
+            ReturnType::Type(_, output) => output.to_token_stream(),
+        };
+
+        // Methods that return a value are sent as requests by default, but either kind of
+        // method can be sent as a message or a request.
+        let is_request = method_options
+            .request
+            .unwrap_or(method.sig.output != ReturnType::Default);
 
-                // Generate the type for the message.
-                #[doc(hidden)]
-                #[allow(bad_style)]
-                struct #message_ty( #( #input_ty, )* );
+        // Reentrant handlers respond with a `ResponseFuture`, e.g. the one returned by
+        // `Context::await_then`. The proxy methods wait for that future as well, so that
+        // requesters get the final value rather than a nested future.
+        let response_ty = response_output(&method.sig);
+        let (requester_output_ty, flatten, flatten_blocking) = match response_ty {
+            Some(response_ty) if is_request => (
+                response_ty.to_token_stream(),
+                quote! { .map(thespian::flatten_response) },
+                quote! { .and_then(thespian::futures::executor::block_on) },
+            ),
+            _ => (output_ty.clone(), quote! {}, quote! {}),
+        };
+        let (send_fn, send_wait_fn, send_blocking_fn) = if is_request {
+            (
+                quote! { send_request },
+                quote! { send_request_wait },
+                quote! { send_request_blocking },
+            )
+        } else {
+            (
+                quote! { send_message },
+                quote! { send_message_wait },
+                quote! { send_message_blocking },
+            )
+        };
+        let wait_method_name = format_ident!("{}_wait", method_name);
+        let blocking_method_name = format_ident!("{}_blocking", method_name);
+        let message = quote! { #message_ty #turbofish ( #( #input_name, )* #phantom_init ) };
 
-                // Generate either a `Message` or a `Request` impl for the message type.
-                impl thespian::Message for #message_ty {
-                    type Actor = #self_ty;
-                    type Output = #output_ty;
+        // If the message can't be sent, the proxy method hands back its arguments rather
+        // than the generated message type.
+        let (unsent_ty, unsent_args) = unsent_message(&input_name, &input_ty);
+        let unsent = quote! {
+            .map_err(|error| error.map_message(
+                |#message_ty ( #( #input_name, )* .. )| #unsent_args,
+                |#unsent_args| #message,
+            ))
+        };
 
-                    fn handle(self, actor: &mut Self::Actor) -> thespian::futures::future::BoxFuture<'_, Self::Output> {
+        // If the message handler is an async fn, we need to append `.await` when we invoke
+        // the method in order to ensure we fully execute the handler.
+        let dot_await = match &method.sig.asyncness {
+            Some(_) => quote! { .await },
+            None => quote! {},
+        };
+
+        // Generate the proxy method, and the expression used to invoke the handler. Trait
+        // methods are invoked using a fully-qualified path, since the trait may not be in
+        // scope where the message type is used.
+        let handler = match interface {
+            Some(interface) => {
+                let (proxy_fn, wait_fn, blocking_fn) = interface_signatures(&method.sig);
+                let response = match method.sig.output {
+                    ReturnType::Default => quote! {},
+                    ReturnType::Type(..) => {
+                        quote! { #flatten .map(thespian::futures::future::FutureExt::boxed) }
+                    }
+                };
+
+                proxy_methods.append_all(quote! {
+                    #proxy_fn {
+                        self.inner.#send_fn(#message) #response #unsent
+                    }
+
+                    #wait_fn {
                         thespian::futures::future::FutureExt::boxed(async move {
-                            actor.#method_name(#( self.#input_index, )*) #dot_await
+                            self.inner.#send_wait_fn(#message).await #response
                         })
                     }
+
+                    #blocking_fn {
+                        self.inner.#send_blocking_fn(#message) #flatten_blocking
+                    }
+                });
+
+                quote! { <#self_ty as #interface>::#method_name(actor, #( #handler_args, )*) }
+            }
+
+            None => {
+                let (proxy_fn_output_ty, blocking_output_ty) = if is_request {
+                    (
+                        quote! { impl std::future::Future<Output = std::result::Result<#requester_output_ty, thespian::RequestError>> },
+                        quote! { std::result::Result<#requester_output_ty, thespian::RequestError> },
+                    )
+                } else {
+                    (quote! { () }, quote! { thespian::Result<()> })
+                };
+
+                proxy_methods.append_all(quote! {
+                    #vis fn #method_name(&self, #( #input_name: #input_ty, )*)
+                        -> std::result::Result<#proxy_fn_output_ty, thespian::SendError<#unsent_ty>>
+                    {
+                        self.inner.#send_fn(#message) #flatten #unsent
+                    }
+
+                    #vis async fn #wait_method_name(&self, #( #input_name: #input_ty, )*) -> thespian::Result<#proxy_fn_output_ty> {
+                        self.inner.#send_wait_fn(#message).await #flatten
+                    }
+
+                    #vis fn #blocking_method_name(&self, #( #input_name: #input_ty, )*) -> #blocking_output_ty {
+                        self.inner.#send_blocking_fn(#message) #flatten_blocking
+                    }
+                });
+
+                quote! { actor.#method_name(#( #handler_args, )*) }
+            }
+        };
+
+        // Reentrant handlers already return a future, so their output is boxed directly
+        // rather than being yielded from an async block.
+        let handler_future = match (response_ty, &method.sig.asyncness) {
+            (None, _) => quote! {
+                thespian::futures::future::FutureExt::boxed(async move {
+                    #handler #dot_await
+                })
+            },
+            (Some(_), Some(_)) => quote! {
+                thespian::futures::future::FutureExt::boxed(#handler)
+            },
+            (Some(_), None) => quote! {
+                thespian::futures::future::FutureExt::boxed(thespian::futures::future::ready(#handler))
+            },
+        };
+
+        // Handlers that take `&self` only need shared access to the actor, so they can be
+        // run concurrently if the actor allows it. Handlers that take the actor's context
+        // need exclusive access to the context, though.
+        let handle_shared = if is_read_only(&method.sig) {
+            quote! {
+                fn handle_shared<'a>(
+                    self,
+                    actor: &'a Self::Actor,
+                ) -> std::result::Result<thespian::futures::future::BoxFuture<'a, Self::Output>, Self>
+                where
+                    for<'x> &'x Self::Actor: Send,
+                {
+                    Ok(#handler_future)
                 }
             }
-        })
-        .collect::<TokenStream>();
+        } else {
+            quote! {}
+        };
+
+        let message_where_clause = with_predicates(
+            where_clause,
+            quote! {
+                #self_ty: thespian::Actor,
+                #message_ty #ty_generics: Send + 'static,
+                #output_ty: Send,
+            },
+        );
+
+        messages.append_all(quote! {
+            // Generate the type for the message.
+            #[doc(hidden)]
+            #[allow(bad_style)]
+            struct #message_ty #generics ( #( #input_ty, )* #phantom_field ) #where_clause;
+
+            // Generate either a `Message` or a `Request` impl for the message type.
+            impl #impl_generics thespian::Message for #message_ty #ty_generics #message_where_clause {
+                type Actor = #self_ty;
+                type Output = #output_ty;
+
+                #[allow(unused_variables)]
+                fn handle<'a>(
+                    self,
+                    actor: &'a mut Self::Actor,
+                    ctx: &'a mut thespian::Context<Self::Actor>,
+                ) -> thespian::futures::future::BoxFuture<'a, Self::Output> {
+                    #handler_future
+                }
+
+                fn type_name() -> &'static str {
+                    #message_name
+                }
+
+                #handle_shared
+            }
+        });
+    }
+
+    let generated = match interface {
+        Some(interface) => {
+            let proxy_trait = proxy_trait_path(interface);
+            quote! {
+                impl #impl_generics #proxy_trait for #proxy_ty #proxy_where_clause {
+                    #proxy_methods
+                }
+
+                #messages
+            }
+        }
+
+        None => quote! {
+            impl #impl_generics #proxy_ty #proxy_where_clause {
+                #proxy_methods
+            }
+
+            #messages
+        },
+    };
 
     // Append the generated code to the original code and return the whole thing as the
     // output.
-    result.append_all(generated);
+    //
+    // NOTE: The generated items are wrapped in an anonymous const so that the message
+    // types are scoped to this impl block. This way impl blocks for different
+    // instantiations of a generic actor, e.g. `impl Slot<u32>` and `impl Slot<u64>`,
+    // can define methods with the same name without their message types colliding.
+    result.append_all(quote! {
+        const _: () = {
+            #generated
+        };
+    });
+    result.into()
+}
+
+/// Generates a proxy-side version of an interface trait.
+///
+/// For a trait `Foo`, this generates a trait `FooProxy` with a method (and matching
+/// `_wait` and `_blocking` methods) for each of the trait's methods. Using
+/// `#[thespian::actor]` on an `impl Foo for MyActor` block then implements `FooProxy`
+/// for `MyActorProxy`, so that callers can depend on `dyn FooProxy` rather than a
+/// concrete proxy type.
+///
+/// Every method of the trait must be implemented in the `#[thespian::actor]` impl
+/// block, including ones with a default implementation.
+#[proc_macro_attribute]
+pub fn interface(
+    _args: proc_macro::TokenStream,
+    tokens: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let mut result = TokenStream::from(tokens.clone());
+
+    // Parse the input as a trait definition, rejecting any other item types.
+    let input = parse_macro_input!(tokens as ItemTrait);
+
+    if !input.generics.params.is_empty() {
+        return Error::new_spanned(
+            &input.generics,
+            "Generic interface traits are not supported",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let vis = &input.vis;
+    let trait_ident = &input.ident;
+    let proxy_trait = format_ident!("{}Proxy", trait_ident);
+    let doc = format!(
+        "Proxy-side interface for actors implementing [`{}`], generated by `#[thespian::interface]`.",
+        trait_ident,
+    );
+
+    // Generate a method on the proxy trait for each method with a receiver, since
+    // associated functions can't be used as messages.
+    let methods = input
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Method(item) => Some(item),
+            _ => None,
+        })
+        .filter(|method| method.sig.receiver().is_some())
+        .map(|method| {
+            let docs = method.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+            let (proxy_fn, wait_fn, blocking_fn) = interface_signatures(&method.sig);
+            quote! {
+                #( #docs )*
+                #proxy_fn;
+
+                #wait_fn;
+
+                #blocking_fn;
+            }
+        })
+        .collect::<TokenStream>();
+
+    result.append_all(quote! {
+        #[doc = #doc]
+        #vis trait #proxy_trait: Send + Sync {
+            #methods
+        }
+    });
     result.into()
 }
 
+/// Returns the normalized names and the types of the method's inputs, excluding the
+/// receiver.
+///
+/// Inputs that use a pattern rather than a plain identifier are given a generated name
+/// based on their position.
+fn message_inputs(sig: &Signature) -> (Vec<Ident>, Vec<&Type>) {
+    typed_inputs(sig)
+        .filter(|arg| !is_context(&arg.ty))
+        .enumerate()
+        .map(|(index, arg)| {
+            let name = match &*arg.pat {
+                Pat::Ident(pat) => pat.ident.clone(),
+                _ => format_ident!("arg{}", index),
+            };
+            (name, &*arg.ty)
+        })
+        .unzip()
+}
+
+/// Generates the type of the message handed back when a proxy method fails to send a
+/// message, along with the pattern/expression for it.
+///
+/// The message is the method's argument if it only takes one, and otherwise a tuple of
+/// its arguments.
+fn unsent_message(input_name: &[Ident], input_ty: &[&Type]) -> (TokenStream, TokenStream) {
+    match (input_name, input_ty) {
+        ([name], [ty]) => (quote! { #ty }, quote! { #name }),
+        _ => (
+            quote! { ( #( #input_ty, )* ) },
+            quote! { ( #( #input_name, )* ) },
+        ),
+    }
+}
+
+/// Generates the arguments passed to a message handler when the message is handled.
+///
+/// Each argument is either a field of the message, or the actor's context for handlers
+/// that take a `&mut Context<Self>` argument.
+fn handler_args(sig: &Signature) -> Vec<TokenStream> {
+    let mut index = 0;
+    typed_inputs(sig)
+        .map(|arg| {
+            if is_context(&arg.ty) {
+                quote! { ctx }
+            } else {
+                let field = Literal::usize_unsuffixed(index);
+                index += 1;
+                quote! { self.#field }
+            }
+        })
+        .collect()
+}
+
+/// Checks if a message handler only needs shared access to the actor, i.e. if it takes
+/// `&self` and doesn't take the actor's context.
+fn is_read_only(sig: &Signature) -> bool {
+    let shared_receiver = sig.inputs.iter().any(|arg| match arg {
+        FnArg::Receiver(receiver) => receiver.reference.is_some() && receiver.mutability.is_none(),
+        FnArg::Typed(_) => false,
+    });
+
+    shared_receiver && !typed_inputs(sig).any(|arg| is_context(&arg.ty))
+}
+
+fn typed_inputs(sig: &Signature) -> impl Iterator<Item = &PatType> {
+    sig.inputs.iter().filter_map(|arg| match arg {
+        FnArg::Typed(arg) => Some(arg),
+        FnArg::Receiver(_) => None,
+    })
+}
+
+/// Checks if an argument is the actor's context, i.e. if it has the type
+/// `&mut Context<...>`.
+///
+/// We can only check the name of the type, so any type named `Context` is treated as
+/// the actor's context.
+fn is_context(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_some() => match &*reference.elem {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Context"),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns the type of the final response for a reentrant handler, i.e. `T` if the
+/// handler returns `ResponseFuture<T>`.
+///
+/// We can only check the name of the type, so any type named `ResponseFuture` with a
+/// single type argument is treated as thespian's `ResponseFuture`.
+fn response_output(sig: &Signature) -> Option<&Type> {
+    let segment = match &sig.output {
+        ReturnType::Type(_, output) => match &**output {
+            Type::Path(path) => path.path.segments.last()?,
+            _ => return None,
+        },
+        ReturnType::Default => return None,
+    };
+    if segment.ident != "ResponseFuture" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Generates the signatures for a method of a proxy-side interface trait, and for the
+/// corresponding `_wait` and `_blocking` methods.
+///
+/// The methods return boxed futures rather than `impl Future` so that the trait can be
+/// used as a trait object.
+fn interface_signatures(sig: &Signature) -> (TokenStream, TokenStream, TokenStream) {
+    let method_name = &sig.ident;
+    let wait_method_name = format_ident!("{}_wait", method_name);
+    let blocking_method_name = format_ident!("{}_blocking", method_name);
+    let (input_name, input_ty) = message_inputs(sig);
+    let (unsent_ty, _) = unsent_message(&input_name, &input_ty);
+
+    // Reentrant methods are flattened, so that the proxy resolves to the final value.
+    let (output_ty, blocking_output_ty) = match &sig.output {
+        ReturnType::Default => (quote! { () }, quote! { thespian::Result<()> }),
+        ReturnType::Type(_, output) => {
+            let output = response_output(sig).unwrap_or(output);
+            (
+                quote! { thespian::ResponseFuture<#output> },
+                quote! { std::result::Result<#output, thespian::RequestError> },
+            )
+        }
+    };
+
+    (
+        quote! {
+            fn #method_name(&self, #( #input_name: #input_ty, )*)
+                -> std::result::Result<#output_ty, thespian::SendError<#unsent_ty>>
+        },
+        quote! {
+            fn #wait_method_name(&self, #( #input_name: #input_ty, )*)
+                -> thespian::futures::future::BoxFuture<'_, thespian::Result<#output_ty>>
+        },
+        quote! {
+            fn #blocking_method_name(&self, #( #input_name: #input_ty, )*) -> #blocking_output_ty
+        },
+    )
+}
+
+/// Returns the path to the proxy-side trait for an interface trait, i.e. the same path
+/// with `Proxy` appended to the trait name.
+fn proxy_trait_path(interface: &Path) -> Path {
+    let mut path = interface.clone();
+    let last = path.segments.last_mut().unwrap();
+    last.ident = format_ident!("{}Proxy", last.ident);
+    path
+}
+
+/// Returns an error if the generics include any lifetime parameters.
+///
+/// Actors must be `'static`, so the only valid lifetime parameter would be one that is
+/// always `'static`, which isn't useful.
+fn reject_lifetimes(generics: &Generics) -> syn::Result<()> {
+    match generics.lifetimes().next() {
+        Some(lifetime) => Err(Error::new_spanned(
+            lifetime,
+            "Actors cannot have lifetime parameters",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Appends `predicates` to the where clause, creating one if necessary.
+fn with_predicates(where_clause: Option<&WhereClause>, predicates: TokenStream) -> WhereClause {
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| WhereClause {
+        where_token: Default::default(),
+        predicates: Punctuated::new(),
+    });
+
+    let predicates = Punctuated::<WherePredicate, Token![,]>::parse_terminated
+        .parse2(predicates)
+        .expect("Failed to parse generated where predicates");
+    where_clause.predicates.extend(predicates);
+
+    where_clause
+}
+
 /// Generates a valid identifier from the given type.
 ///
 /// Returns an error if the type is not a `Type::Path`. Otherwise, the segments of